@@ -13,7 +13,175 @@ rename-placeholder = New name
 rename-empty = Name cannot be empty
 reconnecting = Reconnecting ({ $seconds }s)
 reconnect-not-found = Device not found during discovery
+reconnect-not-found-shared = Device not found during discovery. Another application was scanning at the same time, so results may have been limited; close other Bluetooth settings and try again
 reconnect-pair-failed = Pairing failed
 reconnect-trust-failed = Trusting failed
 reconnect-connect-failed = Connecting failed
+reconnect-unfinished = Reconnecting { $name } ({ $addr }) did not finish
+reconnect-resume = Resume
+reconnect-discard = Discard
+sixaxis-trust-prompt = Trust this PS3 controller? { $name } ({ $addr }) was plugged in over USB
+sixaxis-trust = Trust
+sixaxis-ignore = Ignore
+sixaxis-unplug = { $name } is trusted. Unplug the USB cable and press the PS button to connect over Bluetooth.
+options = Options
+back = Back
+known-devices = Controller settings
+no-known-devices = No saved controller settings
+add-controller = Add controller
+add-controller-help = Put the controller in pairing mode. Nearby controllers show up below.
+add-controller-searching = Searching for controllers…
+add-controller-pair = Pair
+signal-strength = { $rssi } dBm
+show-hidden = Show hidden controllers
+nickname = Nickname
+color-tag = Color tag
+color-none = None
+color-red = Red
+color-orange = Orange
+color-yellow = Yellow
+color-green = Green
+color-blue = Blue
+color-purple = Purple
+favorite = Favorite
+hidden = Hidden
+auto-connect = Connect automatically
+player-number = Player number
+forget-settings = Forget settings
+inhibit-touchpad = Disable touchpad as mouse
+inhibit-motion-sensors = Disable motion sensors
+idle-timeout = Disconnect when idle
+idle-default = Use default
+idle-never = Never
+idle-minutes = { $minutes ->
+    [one] { $minutes } minute
+   *[other] { $minutes } minutes
+}
+inhibit-screen-blanking = Keep screen awake while playing
+inhibit-reason = Game controller in use
+disconnect-on-suspend = Disconnect controllers before suspend
+reconnect-on-resume = Reconnect controllers after resume
+sleep-delay-reason = Disconnecting game controllers
+player-slot = P{ $slot }
+player-slot-none = P-
+player-slot-change = Change player number
+settings = Settings
+settings-devices = Devices
+settings-notifications = Notifications
+settings-behavior = Behavior
+refresh-interval = Refresh every
+refresh-when-closed = Refresh while the popup is closed
+reconnect-timeout = Reconnect timeout
+discovery-timeout = Discovery timeout
+discovery-transport = Discovery transport
+discovery-transport-auto = Automatic
+discovery-transport-bredr = Classic (BR/EDR)
+discovery-transport-le = Low Energy
+seconds = { $seconds } s
+auto-connect-attempts = Automatic connection attempts
+attempts = { $attempts ->
+    [one] { $attempts } attempt
+   *[other] { $attempts } attempts
+}
+auto-repair = Re-pair remembered controllers in pairing mode
+device-filter = Show devices
+device-filter-all = All Bluetooth devices
+device-filter-gamepads = Game controllers only
+adapter = Bluetooth adapter
+adapter-default = Default
+notify-connection = Notify when controllers connect or disconnect
+notify-battery = Notify when battery is low
+battery-low = Low battery
+battery-critical = Critical battery
+battery-threshold-off = Off
+percent = { $percent }%
+panel-display = Panel shows
+panel-display-icon = Icon only
+panel-display-count = Connected controllers
+panel-display-battery = Lowest battery
+notify-connected = Controller connected
+notify-disconnected = Controller disconnected
+notify-battery-low = Controller battery low
+notify-battery-body = { $name } is at { $percent }%
+notify-repaired = Controller paired again
+notify-repair-failed = Re-pairing failed
+notify-repair-failed-body = { $name }: { $error }
+notify-sixaxis-plugged = PS3 controller plugged in
+log-level = Log level
+log-level-error = Errors
+log-level-warn = Warnings
+log-level-info = Info
+log-level-debug = Debug
+log-level-trace = Trace
+settings-diagnostics = Diagnostics
+anonymize-diagnostics = Anonymize device addresses and the computer name
+diagnostics-copy = Copy diagnostics
+diagnostics-save = Save diagnostics
+diagnostics-copied = Diagnostics copied to the clipboard
+diagnostics-saved = Diagnostics saved to { $path }
+compatibility = Compatibility check
+compatibility-check = Check setup
+compatibility-checking = Checking Bluetooth and kernel setup…
+compatibility-no-controllers = No paired or connected controllers
+compatibility-ok = No problems found
+compatibility-fix = Fix: { $fix }
+compatibility-apply = Apply fix
+compatibility-fix-applied = Bluetooth configuration updated
+compatibility-confirm-security = This lets any nearby device connect as a keyboard or mouse without pairing (CVE-2023-45866). Only do this while you use a DualShock 3, and keep Bluetooth off otherwise.
+compatibility-apply-anyway = Apply anyway
+compatibility-restart-needed = Restart Bluetooth for the changes to take effect.
+compatibility-restart = Restart Bluetooth
+compatibility-restart-confirm = Every Bluetooth device, including keyboards, mice and headsets, disconnects while bluetooth.service restarts.
+compatibility-restarted = bluetooth.service restarted
+model-dualshock3 = DualShock 3
+model-dualshock4 = DualShock 4
+model-dualsense = DualSense
+model-xbox = Xbox Wireless Controller
+model-switch-pro = Switch Pro Controller
+model-joycon = Joy-Con
+model-wiimote = Wii Remote
+model-8bitdo = 8BitDo controller
+model-other = Other controller
+pairing-steps-playstation = Hold Share (Create on a DualSense) and the PS button together until the light bar blinks quickly.
+pairing-steps-dualshock3 = Connect the controller with a USB cable first; it can't be paired over Bluetooth alone.
+pairing-steps-xbox = Turn the controller on, then hold the pair button on top until the Xbox button flashes quickly.
+pairing-steps-nintendo = Hold the small sync button (on top of a Pro Controller, on the rail of a Joy-Con) until the player lights run back and forth.
+pairing-steps-8bitdo = Turn the controller on with Start+Y (Switch), Start+X (XInput) or Start+B (Android) depending on the mode you want, then hold the pair button for 3 seconds until the LED blinks quickly.
+pairing-steps-wiimote = Open the battery cover and press the red sync button.
+pairing-steps-other = Hold the controller's pairing or home button until its light blinks quickly.
+finding-classic-bonded-only = ClassicBondedOnly is enabled
+finding-classic-bonded-only-why = DualShock 3 controllers connect without a proper bond, so BlueZ rejects them while ClassicBondedOnly is on. Turning it off also lets unpaired devices inject keystrokes (CVE-2023-45866).
+finding-classic-bonded-only-fix = Set ClassicBondedOnly=false in the [General] section of /etc/bluetooth/input.conf and restart bluetooth.service.
+finding-uhid-missing = uhid is not available
+finding-uhid-missing-why = BlueZ handles HID in userspace (UserspaceHID) and needs /dev/uhid to create the controller's input devices.
+finding-uhid-missing-fix = Load the uhid kernel module (modprobe uhid) or set UserspaceHID=false in /etc/bluetooth/input.conf.
+finding-fast-connectable = FastConnectable is off
+finding-fast-connectable-why = Xbox controllers stop paging the adapter quickly and often fail to reconnect unless it is fast connectable.
+finding-fast-connectable-fix = Set FastConnectable = true in the [General] section of /etc/bluetooth/main.conf and restart bluetooth.service.
+finding-just-works-repairing = JustWorksRepairing is set to never
+finding-just-works-repairing-why = This controller re-pairs without a PIN, which BlueZ refuses for already-known devices unless JustWorksRepairing allows it.
+finding-just-works-repairing-fix = Set JustWorksRepairing = always in the [General] section of /etc/bluetooth/main.conf and restart bluetooth.service.
+finding-ertm-enabled = ERTM is enabled
+finding-ertm-enabled-why = Xbox controllers with older firmware fail to connect or keep disconnecting while L2CAP ERTM is enabled.
+finding-ertm-enabled-fix = Update the controller firmware, or set the bluetooth module parameter disable_ertm=Y.
+finding-xpadneo-missing = xpadneo is not loaded
+finding-xpadneo-missing-why = Without the xpadneo driver, Xbox controllers fall back to generic HID with wrong button mappings and no rumble.
+finding-xpadneo-missing-fix = Install xpadneo (usually packaged as xpadneo-dkms) and reconnect the controller.
+finding-hid-nintendo-missing = hid-nintendo is not loaded
+finding-hid-nintendo-missing-why = Nintendo controllers need the hid-nintendo driver for correct buttons, sticks and player LEDs.
+finding-hid-nintendo-missing-fix = Load the hid_nintendo kernel module (modprobe hid_nintendo) and reconnect the controller.
+bluetooth-unavailable = Bluetooth service unavailable. Waiting for bluetoothd to start…
+bluetooth-available = Bluetooth service is back
+history = History
+history-clear = Clear
+history-copy = Copy to clipboard
+history-dismiss = Dismiss
+history-disconnected = Disconnected
+history-renamed = Renamed
+history-removed = Removed
+history-reconnected = Reconnected
+history-auto-connected = Connected automatically
+history-repaired = Paired again
+history-added = Paired and connected
+history-sixaxis-trusted = PS3 controller trusted
 error-loading = Error: { $error }
\ No newline at end of file
@@ -1,73 +1,439 @@
-use super::{AppModel, ConnectedDevice, DeviceData, Message};
+use super::{
+    AppModel, AppSetting, AutoConnectState, ConnectedDevice, DeviceData, DeviceOp, DeviceSetting,
+    DiagnosticsTarget, HistoryEntry, HistoryKind, Message, Page,
+};
 use crate::app::APP_ID;
-use crate::config::Config;
+use crate::config::{
+    AUTO_CONNECT_ATTEMPT_PRESETS, BATTERY_THRESHOLD_PRESETS, ColorTag, Config, DeviceFilter,
+    DiscoveryTransport, IDLE_TIMEOUT_PRESETS, LogLevel, PanelDisplay, REFRESH_INTERVAL_PRESETS,
+    TIMEOUT_PRESETS,
+};
 use crate::fl;
+use crate::journal::{self, JournalEntry, ReconnectStep};
+use crate::logging;
 use crate::services::{
-    connect_device, disconnect_device, discover_device_by_address, list_connected_devices,
-    list_paired_devices, pair_device, read_ps_controller_battery, remove_device,
-    rename_paired_device, trust_device,
+    ActivityMonitor, Bluez, ControllerModel, ControllerReport, DiscoveryFilter, IdleInhibitor,
+    InputNodeKind, ListFilter, PLAYER_SLOTS, PresenceEvent, apply_fix, check_compatibility,
+    collect_diagnostics, connect_device, detect_model, disconnect_device,
+    discover_device_by_address, get_device_property, inhibit_idle, list_adapters,
+    list_connected_devices, list_input_nodes, list_nearby_gamepads, list_paired_devices,
+    list_untrusted_sixaxis, pair_device, read_device_state, read_ps_controller_battery,
+    receive_prepare_for_sleep, receive_presence_events, receive_untrusted_sixaxis, remove_device,
+    rename_paired_device, restart_bluetooth, send_notification, set_device_property,
+    set_discovering, set_input_inhibited, set_player_leds, start_discovery, take_sleep_delay,
+    trust_device,
 };
+use chrono::Local;
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
-use cosmic::iced::futures::SinkExt;
+use cosmic::iced::futures::{SinkExt, StreamExt};
 use cosmic::iced::{Limits, Subscription, time, window::Id};
 use cosmic::iced_winit::commands::popup::{destroy_popup, get_popup};
 use cosmic::prelude::*;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use zbus::Connection;
+use zbus::zvariant::Value;
 
 pub fn init(core: cosmic::Core, _flags: ()) -> (AppModel, Task<cosmic::Action<Message>>) {
-    let config = cosmic_config::Config::new(APP_ID, Config::VERSION)
-        .map(|context| match Config::get_entry(&context) {
-            Ok(config) => config,
-            Err((_errors, config)) => config,
-        })
+    let config_handler = cosmic_config::Config::new(APP_ID, Config::VERSION).ok();
+    let config = config_handler
+        .as_ref()
+        .map(crate::config::migrate::load)
         .unwrap_or_default();
+    logging::set_level(config.log_level);
 
-    let app = AppModel {
+    let idle_timeout_options = std::iter::once(fl!("idle-default"))
+        .chain(IDLE_TIMEOUT_PRESETS.iter().map(|minutes| match *minutes {
+            0 => fl!("idle-never"),
+            minutes => fl!("idle-minutes", minutes = minutes),
+        }))
+        .collect();
+    let color_options = std::iter::once(fl!("color-none"))
+        .chain(ColorTag::ALL.iter().map(|color| color_name(*color)))
+        .collect();
+    let player_slot_options = (1..=PLAYER_SLOTS)
+        .map(|slot| fl!("player-slot", slot = slot))
+        .collect();
+    let refresh_interval_options = REFRESH_INTERVAL_PRESETS
+        .iter()
+        .map(|seconds| fl!("seconds", seconds = seconds))
+        .collect();
+    let timeout_options = TIMEOUT_PRESETS
+        .iter()
+        .map(|seconds| fl!("seconds", seconds = seconds))
+        .collect();
+    let battery_threshold_options = BATTERY_THRESHOLD_PRESETS
+        .iter()
+        .map(|percent| match *percent {
+            0 => fl!("battery-threshold-off"),
+            percent => fl!("percent", percent = percent),
+        })
+        .collect();
+    let device_filter_options = DeviceFilter::ALL
+        .iter()
+        .map(|filter| match filter {
+            DeviceFilter::All => fl!("device-filter-all"),
+            DeviceFilter::Gamepads => fl!("device-filter-gamepads"),
+        })
+        .collect();
+    let discovery_transport_options = DiscoveryTransport::ALL
+        .iter()
+        .map(|transport| match transport {
+            DiscoveryTransport::Auto => fl!("discovery-transport-auto"),
+            DiscoveryTransport::BrEdr => fl!("discovery-transport-bredr"),
+            DiscoveryTransport::Le => fl!("discovery-transport-le"),
+        })
+        .collect();
+    let panel_display_options = PanelDisplay::ALL
+        .iter()
+        .map(|display| match display {
+            PanelDisplay::Icon => fl!("panel-display-icon"),
+            PanelDisplay::ConnectedCount => fl!("panel-display-count"),
+            PanelDisplay::LowestBattery => fl!("panel-display-battery"),
+        })
+        .collect();
+    let log_level_options = LogLevel::ALL
+        .iter()
+        .map(|level| match level {
+            LogLevel::Error => fl!("log-level-error"),
+            LogLevel::Warn => fl!("log-level-warn"),
+            LogLevel::Info => fl!("log-level-info"),
+            LogLevel::Debug => fl!("log-level-debug"),
+            LogLevel::Trace => fl!("log-level-trace"),
+        })
+        .collect();
+    let auto_connect_attempt_options = AUTO_CONNECT_ATTEMPT_PRESETS
+        .iter()
+        .map(|attempts| fl!("attempts", attempts = attempts))
+        .collect();
+    let mut app = AppModel {
         core,
         config,
+        config_handler,
+        idle_timeout_options,
+        color_options,
+        player_slot_options,
+        refresh_interval_options,
+        timeout_options,
+        battery_threshold_options,
+        device_filter_options,
+        discovery_transport_options,
+        log_level_options,
+        panel_display_options,
+        adapter_options: vec![fl!("adapter-default")],
+        auto_connect_attempt_options,
+        refresh_generation: 1,
+        bluez_available: true,
         ..Default::default()
     };
+    load_pending_reconnects(&mut app);
 
-    (
-        app,
-        cosmic::task::future(async { Message::DataLoaded(load_devices().await) }),
-    )
+    // Devices are loaded once the shared BlueZ connection is up.
+    (app, Task::none())
+}
+
+fn color_name(color: ColorTag) -> String {
+    match color {
+        ColorTag::Red => fl!("color-red"),
+        ColorTag::Orange => fl!("color-orange"),
+        ColorTag::Yellow => fl!("color-yellow"),
+        ColorTag::Green => fl!("color-green"),
+        ColorTag::Blue => fl!("color-blue"),
+        ColorTag::Purple => fl!("color-purple"),
+    }
 }
 
+const PRESENCE_SCAN_WINDOW: Duration = Duration::from_secs(10);
+const PRESENCE_SCAN_INTERVAL: Duration = Duration::from_secs(60);
+const AUTO_CONNECT_BACKOFF: Duration = Duration::from_secs(5);
+const AUTO_CONNECT_OUT_OF_RANGE: Duration = Duration::from_secs(180);
+const REPAIR_COOLDOWN: Duration = Duration::from_secs(60);
+const IDLE_INHIBIT_RETRY: Duration = Duration::from_secs(30);
+const HISTORY_LIMIT: usize = 20;
+const ACTIVITY_RETRY_MIN: Duration = Duration::from_secs(2);
+const ACTIVITY_RETRY_MAX: Duration = Duration::from_secs(60);
+
 pub fn subscription(app: &AppModel) -> Subscription<Message> {
     struct RefreshSubscription;
+    struct ActivitySubscription;
+    struct SleepSubscription;
+    struct PresenceSubscription;
+    struct BluezSubscription;
+    struct NearbySubscription;
+    struct SixaxisSubscription;
 
-    Subscription::batch(vec![
+    let threshold = app.config.idle_stick_threshold;
+    let activity = app
+        .connected
+        .iter()
+        .filter(|dev| app.config.watches_activity(&dev.addr))
+        .map(|dev| {
+            let addr = dev.addr.clone();
+            Subscription::run_with_id(
+                (
+                    std::any::TypeId::of::<ActivitySubscription>(),
+                    addr.clone(),
+                    threshold,
+                ),
+                cosmic::iced::stream::channel(4, move |mut channel| async move {
+                    // The event node shows up a little after BlueZ reports the connection and is
+                    // replaced when the controller reconnects, so keep looking with backoff.
+                    let mut backoff = ACTIVITY_RETRY_MIN;
+                    loop {
+                        let mut monitor = match ActivityMonitor::open(&addr, threshold) {
+                            Ok(Some(monitor)) => monitor,
+                            Ok(None) => {
+                                tracing::debug!(
+                                    "no input node for {addr}, retrying in {backoff:?}"
+                                );
+                                tokio::time::sleep(backoff).await;
+                                backoff = (backoff * 2).min(ACTIVITY_RETRY_MAX);
+                                continue;
+                            }
+                            Err(err) => {
+                                let error = format!("Failed to watch controller input: {err}");
+                                let _ = channel
+                                    .send(Message::ActivityMonitorFailed(addr, error))
+                                    .await;
+                                return;
+                            }
+                        };
+                        backoff = ACTIVITY_RETRY_MIN;
+
+                        let _ = channel
+                            .send(Message::ControllerActivity(addr.clone()))
+                            .await;
+                        let mut last_sent = Instant::now();
+                        let err = loop {
+                            if let Err(err) = monitor.next_activity().await {
+                                break err;
+                            }
+                            if last_sent.elapsed() >= Duration::from_secs(1) {
+                                let _ = channel
+                                    .send(Message::ControllerActivity(addr.clone()))
+                                    .await;
+                                last_sent = Instant::now();
+                            }
+                        };
+                        tracing::debug!("lost the input node for {addr}: {err}");
+                    }
+                }),
+            )
+        });
+
+    let delay_sleep = app.config.disconnect_on_suspend;
+    let sleep = Subscription::run_with_id(
+        (std::any::TypeId::of::<SleepSubscription>(), delay_sleep),
+        cosmic::iced::stream::channel(4, move |mut channel| async move {
+            let Ok(conn) = Connection::system().await else {
+                return;
+            };
+            let Ok(mut signals) = receive_prepare_for_sleep(&conn).await else {
+                return;
+            };
+
+            let reason = fl!("sleep-delay-reason");
+            let mut delay = None;
+            if delay_sleep {
+                delay = take_sleep_delay(&conn, &reason).await.ok().map(Arc::new);
+            }
+
+            while let Some(sleeping) = signals.next().await {
+                if sleeping {
+                    let _ = channel.send(Message::PrepareForSleep(delay.take())).await;
+                } else {
+                    if delay_sleep {
+                        delay = take_sleep_delay(&conn, &reason).await.ok().map(Arc::new);
+                    }
+                    let _ = channel.send(Message::Resumed).await;
+                }
+            }
+        }),
+    );
+
+    let interval = app.config.refresh_interval();
+    let refresh = if app.config.refresh_when_closed || app.popup.is_some() {
         Subscription::run_with_id(
-            std::any::TypeId::of::<RefreshSubscription>(),
+            (std::any::TypeId::of::<RefreshSubscription>(), interval),
             cosmic::iced::stream::channel(4, move |mut channel| async move {
                 loop {
                     let _ = channel.send(Message::Refresh).await;
-                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    tokio::time::sleep(interval).await;
+                }
+            }),
+        )
+    } else {
+        Subscription::none()
+    };
+
+    let waiting = app.paired.iter().any(|(addr, _)| {
+        app.config.auto_connects(addr) && app.connected.iter().all(|dev| &dev.addr != addr)
+    });
+    let adapter = app.config.adapter.clone();
+    let presence = if let Some(bluez) = app.bluez.clone()
+        && app.bluez_available
+        && (waiting || (app.loaded && app.config.awaits_repair(&app.bonded)))
+    {
+        Subscription::run_with_id(
+            (
+                std::any::TypeId::of::<PresenceSubscription>(),
+                adapter.clone(),
+            ),
+            cosmic::iced::stream::channel(16, move |mut channel| async move {
+                let Ok(mut events) = receive_presence_events(bluez.connection()).await else {
+                    return;
+                };
+                let Ok(conn) = Connection::system().await else {
+                    return;
+                };
+                let session = bluez.with_connection(conn);
+
+                // Scan in short windows so the adapter isn't discovering all the time. BlueZ
+                // stops our discovery session when this connection is dropped.
+                let mut scanning = false;
+                let timer = tokio::time::sleep(Duration::ZERO);
+                tokio::pin!(timer);
+                loop {
+                    tokio::select! {
+                        () = &mut timer => {
+                            scanning = !scanning;
+                            let _ = set_discovering(&session, adapter.as_deref(), scanning).await;
+                            let next = if scanning {
+                                PRESENCE_SCAN_WINDOW
+                            } else {
+                                PRESENCE_SCAN_INTERVAL - PRESENCE_SCAN_WINDOW
+                            };
+                            timer.as_mut().reset(tokio::time::Instant::now() + next);
+                        }
+                        event = events.next() => {
+                            let Some(event) = event else {
+                                break;
+                            };
+                            let _ = channel.send(Message::DevicePresence(event)).await;
+                        }
+                    }
+                }
+            }),
+        )
+    } else {
+        Subscription::none()
+    };
+
+    let adapter = app.config.adapter.clone();
+    let filter = DiscoveryFilter {
+        hid_only: false,
+        ..app.config.discovery_filter()
+    };
+    let nearby = if let Some(bluez) = app.bluez.clone()
+        && app.bluez_available
+        && app.popup.is_some()
+        && app.page == Page::AddController
+    {
+        Subscription::run_with_id(
+            (
+                std::any::TypeId::of::<NearbySubscription>(),
+                adapter.clone(),
+                filter.transport,
+            ),
+            cosmic::iced::stream::channel(4, move |mut channel| async move {
+                let Ok(conn) = Connection::system().await else {
+                    return;
+                };
+                let session = bluez.with_connection(conn);
+                if let Err(err) = start_discovery(&session, adapter.as_deref(), &filter).await {
+                    let error = format!("Failed to start discovery: {err}");
+                    let _ = channel.send(Message::NearbyDiscoveryFailed(error)).await;
+                    return;
+                }
+
+                // BlueZ stops our discovery session once this connection is dropped, which
+                // happens when the page is left or the popup closes.
+                std::future::pending::<()>().await;
+            }),
+        )
+    } else {
+        Subscription::none()
+    };
+
+    let sixaxis = if let Some(bluez) = app.bluez.clone() {
+        Subscription::run_with_id(
+            std::any::TypeId::of::<SixaxisSubscription>(),
+            cosmic::iced::stream::channel(4, move |mut channel| async move {
+                let Ok(mut plugged) = receive_untrusted_sixaxis(&bluez).await else {
+                    return;
+                };
+                // Subscribed first so nothing plugged in during the initial scan is missed.
+                for (addr, name) in list_untrusted_sixaxis(&bluez) {
+                    let _ = channel.send(Message::SixaxisPlugged(addr, name)).await;
+                }
+                while let Some((addr, name)) = plugged.next().await {
+                    let _ = channel.send(Message::SixaxisPlugged(addr, name)).await;
                 }
             }),
-        ),
+        )
+    } else {
+        Subscription::none()
+    };
+
+    let bluez = Subscription::run_with_id(
+        std::any::TypeId::of::<BluezSubscription>(),
+        cosmic::iced::stream::channel(4, |mut channel| async move {
+            let bluez = match Bluez::connect().await {
+                Ok(bluez) => bluez,
+                Err(err) => {
+                    tracing::error!("failed to connect to BlueZ: {err}");
+                    let _ = channel.send(Message::BluezAvailability(false)).await;
+                    return;
+                }
+            };
+
+            let mut available = bluez.availability();
+            let _ = channel.send(Message::BluezConnected(bluez)).await;
+            loop {
+                let value = *available.borrow_and_update();
+                let _ = channel.send(Message::BluezAvailability(value)).await;
+                if available.changed().await.is_err() {
+                    break;
+                }
+            }
+        }),
+    );
+
+    let spinner = if app.popup.is_some() && !app.operations.is_empty() {
+        time::every(Duration::from_millis(100)).map(|_| Message::SpinnerTick)
+    } else {
+        Subscription::none()
+    };
+
+    Subscription::batch(vec![
+        refresh,
         time::every(Duration::from_secs(1)).map(|_| Message::Tick),
+        spinner,
         app.core
             .watch_config::<Config>(APP_ID)
             .map(|update| Message::UpdateConfig(update.config)),
+        Subscription::batch(activity),
+        sleep,
+        presence,
+        nearby,
+        sixaxis,
+        bluez,
     ])
 }
 
 pub fn update(app: &mut AppModel, message: Message) -> Task<cosmic::Action<Message>> {
+    let _span = tracing::trace_span!("update", ?message).entered();
+
     match message {
         Message::Refresh => {
-            if !app.reconnecting.is_empty() || app.renaming_addr.is_some() {
+            if !app.bluez_available || !app.reconnecting.is_empty() || app.renaming_addr.is_some() {
                 return Task::none();
             }
-            return cosmic::task::future(async { Message::DataLoaded(load_devices().await) });
+            return refresh_devices(app);
         }
         Message::Tick => {
-            if app.reconnecting.is_empty() {
-                return Task::none();
-            }
-
             app.reconnecting.retain(|_, remaining| {
                 if *remaining > 0 {
                     *remaining -= 1;
@@ -76,14 +442,72 @@ pub fn update(app: &mut AppModel, message: Message) -> Task<cosmic::Action<Messa
                     false
                 }
             });
+
+            let idle: Vec<String> = app
+                .last_activity
+                .iter()
+                .filter(|(addr, last)| {
+                    app.config
+                        .idle_timeout(addr)
+                        .is_some_and(|timeout| last.elapsed() >= timeout)
+                })
+                .map(|(addr, _)| addr.clone())
+                .collect();
+
+            for addr in &idle {
+                app.last_activity.remove(addr);
+                app.auto_connect_paused.insert(addr.clone());
+            }
+
+            if app.page == Page::AddController {
+                refresh_nearby(app);
+            }
+
+            let mut tasks: Vec<_> = idle
+                .into_iter()
+                .map(|addr| enqueue_operation(app, addr, DeviceOp::Disconnect))
+                .collect();
+            tasks.push(sync_idle_inhibitor(app));
+            return Task::batch(tasks);
+        }
+        Message::SpinnerTick => {
+            app.spinner_frame = app.spinner_frame.wrapping_add(1);
         }
-        Message::DataLoaded(Ok(data)) => {
-            app.connected = data.connected;
+        Message::DataLoaded(generation, _) if generation < app.refresh_generation => {}
+        Message::DataLoaded(_, data) => {
+            let previous = std::mem::replace(&mut app.connected, data.connected);
+            app.player_leds
+                .retain(|addr, _| app.connected.iter().any(|dev| &dev.addr == addr));
+            app.inhibit_applied
+                .retain(|addr| app.connected.iter().any(|dev| &dev.addr == addr));
+            app.last_activity
+                .retain(|addr, _| app.connected.iter().any(|dev| &dev.addr == addr));
+            app.auto_connect
+                .retain(|addr, _| app.connected.iter().all(|dev| &dev.addr != addr));
+            app.sixaxis_unplug
+                .retain(|(addr, _)| app.connected.iter().all(|dev| &dev.addr != addr));
             let mut paired = data.paired;
             app.paired_names = paired
                 .iter()
                 .map(|(addr, name)| (addr.clone(), name.clone()))
                 .collect();
+            let gamepads: Vec<_> = app
+                .connected
+                .iter()
+                .filter(|dev| dev.gamepad)
+                .map(|dev| dev.addr.clone())
+                .collect();
+            update_config(app, |config| {
+                for (addr, name) in &paired {
+                    if let Some(settings) = config.devices.get_mut(addr) {
+                        settings.alias = Some(name.clone());
+                    }
+                }
+                for addr in &gamepads {
+                    config.device_mut(addr).game_controller = true;
+                }
+            });
+            app.bonded = data.bonded;
 
             for (addr, _) in &app.reconnecting {
                 if paired.iter().all(|(known, _)| known != addr) {
@@ -97,22 +521,58 @@ pub fn update(app: &mut AppModel, message: Message) -> Task<cosmic::Action<Messa
             }
 
             app.paired = paired;
-            app.last_error = None;
+            app.adapter_options = std::iter::once(fl!("adapter-default"))
+                .chain(
+                    data.adapters
+                        .iter()
+                        .map(|(name, alias)| match alias.as_str() {
+                            "" => name.clone(),
+                            alias => format!("{alias} ({name})"),
+                        }),
+                )
+                .collect();
+            app.adapters = data.adapters;
+
+            let mut tasks = vec![sync_player_leds(app), sync_input_inhibit(app)];
+            tasks.extend(notify_connection_changes(app, &previous));
+            tasks.extend(notify_battery_levels(app));
+            app.loaded = true;
+            return Task::batch(tasks);
         }
-        Message::DataLoaded(Err(error)) => {
-            app.last_error = Some(error);
+        Message::BluezConnected(bluez) => {
+            app.bluez = Some(bluez);
+            return resume_operations(app);
+        }
+        Message::BluezAvailability(available) => {
+            if available == app.bluez_available {
+                return Task::none();
+            }
+
+            app.bluez_available = available;
+            if !available {
+                log_error(app, None, fl!("bluetooth-unavailable"));
+                return Task::none();
+            }
+
+            log_event(app, None, fl!("bluetooth-available"));
+            load_pending_reconnects(app);
+            return resume_operations(app);
         }
         Message::DisconnectDevice(addr) => {
-            return cosmic::task::future(async move {
-                Message::DisconnectResult(disconnect_by_addr(addr).await)
-            });
+            app.auto_connect_paused.insert(addr.clone());
+            return enqueue_operation(app, addr, DeviceOp::Disconnect);
         }
-        Message::DisconnectResult(result) => {
+        Message::DisconnectResult(addr, result) => {
+            let next = finish_operation(app, &addr);
+            if app.sleep_disconnecting.remove(&addr) && app.sleep_disconnecting.is_empty() {
+                app.sleep_delay = None;
+            }
             if let Err(error) = result {
-                app.last_error = Some(error);
-            } else {
-                return cosmic::task::future(async { Message::DataLoaded(load_devices().await) });
+                log_error(app, Some(&addr), error);
+                return next;
             }
+            log_event(app, Some(&addr), fl!("history-disconnected"));
+            return Task::batch([next, refresh_devices(app)]);
         }
         Message::RenameStart(addr, current) => {
             app.renaming_addr = Some(addr);
@@ -128,37 +588,36 @@ pub fn update(app: &mut AppModel, message: Message) -> Task<cosmic::Action<Messa
         Message::RenameSubmit(addr) => {
             let new_name = app.renaming_value.trim().to_string();
             if new_name.is_empty() {
-                app.last_error = Some(fl!("rename-empty").to_string());
+                log_error(app, Some(&addr), fl!("rename-empty"));
                 return Task::none();
             }
             app.renaming_addr = None;
             app.renaming_value.clear();
-            return cosmic::task::future(async move {
-                Message::RenameResult(rename_by_addr(addr, new_name).await)
-            });
+            return enqueue_operation(app, addr, DeviceOp::Rename(new_name));
         }
-        Message::RenameResult(result) => {
+        Message::RenameResult(addr, result) => {
+            let next = finish_operation(app, &addr);
             if let Err(error) = result {
-                app.last_error = Some(error);
-            } else {
-                return cosmic::task::future(async { Message::DataLoaded(load_devices().await) });
+                log_error(app, Some(&addr), error);
+                return next;
             }
+            log_event(app, Some(&addr), fl!("history-renamed"));
+            return Task::batch([next, refresh_devices(app)]);
         }
         Message::RemoveDevice(addr) => {
-            return cosmic::task::future(async move {
-                Message::RemoveResult(remove_by_addr(addr).await)
-            });
+            return enqueue_operation(app, addr, DeviceOp::Remove);
         }
-        Message::RemoveResult(result) => {
+        Message::RemoveResult(addr, result) => {
+            let next = finish_operation(app, &addr);
             if let Err(error) = result {
-                app.last_error = Some(error);
-            } else {
-                return cosmic::task::future(async { Message::DataLoaded(load_devices().await) });
+                log_error(app, Some(&addr), error);
+                return next;
             }
+            log_event(app, Some(&addr), fl!("history-removed"));
+            return Task::batch([next, refresh_devices(app)]);
         }
         Message::ReconnectDevice(addr) => {
-            app.reconnecting.insert(addr.clone(), 60);
-            let name = app
+            let alias = app
                 .paired_names
                 .get(&addr)
                 .cloned()
@@ -169,21 +628,544 @@ pub fn update(app: &mut AppModel, message: Message) -> Task<cosmic::Action<Messa
                         .map(|(_, name)| name.clone())
                 })
                 .unwrap_or_else(|| addr.clone());
-            return cosmic::task::future(async move {
-                Message::ReconnectResult(reconnect_by_addr(addr, name).await)
+            let entry = JournalEntry {
+                addr: addr.clone(),
+                alias,
+                trusted: true,
+                blocked: false,
+                wake_allowed: None,
+                settings: app.config.device(&addr).cloned(),
+                modalias: cached_modalias(app, &addr),
+                step: ReconnectStep::Removing,
+            };
+            app.auto_connect.remove(&addr);
+            return enqueue_operation(app, addr, DeviceOp::Reconnect(entry));
+        }
+        Message::ReconnectResult(addr, result) => {
+            app.reconnecting.remove(&addr);
+            let next = finish_operation(app, &addr);
+            load_pending_reconnects(app);
+            match result {
+                Ok(entry) => {
+                    restore_applet_settings(app, entry);
+                    log_event(app, Some(&addr), fl!("history-reconnected"));
+                    return Task::batch([next, refresh_devices(app)]);
+                }
+                Err(error) => {
+                    log_error(app, Some(&addr), error);
+                    return next;
+                }
+            }
+        }
+        Message::ResumeReconnect(addr) => {
+            let Some(index) = app
+                .pending_reconnects
+                .iter()
+                .position(|entry| entry.addr == addr)
+            else {
+                return Task::none();
+            };
+            let entry = app.pending_reconnects.remove(index);
+            return enqueue_operation(app, addr, DeviceOp::Reconnect(entry));
+        }
+        Message::DiscardReconnect(addr) => {
+            app.pending_reconnects.retain(|entry| entry.addr != addr);
+            if let Err(err) = journal::finish(&addr) {
+                log_error(
+                    app,
+                    Some(&addr),
+                    format!("Failed to update reconnect journal: {err}"),
+                );
+            }
+        }
+        Message::CyclePlayerSlot(addr) => {
+            let next = app
+                .config
+                .player_slot(&addr)
+                .map_or(1, |slot| slot % PLAYER_SLOTS + 1);
+            return set_player_slot(app, addr, next);
+        }
+        Message::SetPlayerSlot(addr, slot) => {
+            return set_player_slot(app, addr, slot);
+        }
+        Message::PlayerLedsResult(addr, slot, result) => match result {
+            Ok(true) => {}
+            Ok(false) => {
+                if app.player_leds.get(&addr) == Some(&slot) {
+                    app.player_leds.remove(&addr);
+                }
+            }
+            Err(error) => {
+                log_error(app, Some(&addr), error);
+            }
+        },
+        Message::OpenPage(page) => {
+            let previous = std::mem::replace(&mut app.page, page.clone());
+            app.page_history.push(previous);
+
+            if page == Page::AddController {
+                refresh_nearby(app);
+            }
+
+            if page == Page::Compatibility {
+                app.compatibility = None;
+                let bluez = shared_bluez(app);
+                return cosmic::task::future(async move {
+                    Message::CompatibilityLoaded(load_compatibility(bluez))
+                });
+            }
+
+            if let Page::Device(addr) = page {
+                app.nickname_value = app
+                    .config
+                    .device(&addr)
+                    .and_then(|settings| settings.nickname.clone())
+                    .unwrap_or_default();
+                return cosmic::task::future(async move {
+                    let result = list_input_nodes(&addr)
+                        .map(|nodes| nodes.into_iter().map(|node| node.kind).collect())
+                        .map_err(|err| format!("Failed to list input nodes: {err}"));
+                    Message::InputNodesLoaded(addr, result)
+                });
+            }
+        }
+        Message::Back => {
+            app.page = app.page_history.pop().unwrap_or_default();
+        }
+        Message::SetShowHidden(show) => {
+            app.show_hidden = show;
+        }
+        Message::NicknameInput(value) => {
+            app.nickname_value = value;
+        }
+        Message::NicknameSubmit(addr) => {
+            let nickname = app.nickname_value.trim().to_string();
+            update_config(app, |config| {
+                config.device_mut(&addr).nickname = (!nickname.is_empty()).then_some(nickname);
+            });
+        }
+        Message::SetDeviceSetting(addr, setting) => {
+            if let DeviceSetting::AutoConnect(_) = setting {
+                app.auto_connect.remove(&addr);
+                app.auto_connect_paused.remove(&addr);
+            }
+            update_config(app, |config| {
+                let settings = config.device_mut(&addr);
+                match setting {
+                    DeviceSetting::Color(color) => settings.color = color,
+                    DeviceSetting::Hidden(hidden) => settings.hidden = hidden,
+                    DeviceSetting::Favorite(favorite) => settings.favorite = favorite,
+                    DeviceSetting::AutoConnect(enabled) => settings.auto_connect = enabled,
+                    DeviceSetting::IdleTimeout(minutes) => settings.idle_timeout_minutes = minutes,
+                }
+            });
+        }
+        Message::ForgetDevice(addr) => {
+            update_config(app, |config| {
+                config.devices.remove(&addr);
             });
+            app.player_leds.remove(&addr);
+            if app.page == Page::Device(addr) {
+                app.page = app.page_history.pop().unwrap_or_default();
+            }
         }
-        Message::ReconnectResult(result) => match result {
-            Ok((addr, _found)) => {
-                app.reconnecting.remove(&addr);
-                return cosmic::task::future(async { Message::DataLoaded(load_devices().await) });
+        Message::InputNodesLoaded(addr, result) => match result {
+            Ok(kinds) => {
+                app.input_nodes.insert(addr, kinds);
             }
             Err(error) => {
-                app.last_error = Some(error);
+                log_error(app, Some(&addr), error);
+            }
+        },
+        Message::SetInputInhibited(addr, kind, inhibited) => {
+            update_config(app, |config| {
+                config.set_input_inhibited(&addr, kind, inhibited)
+            });
+            return apply_input_inhibit(addr, vec![kind], inhibited);
+        }
+        Message::InputInhibitResult(addr, result) => match result {
+            Ok(0) => {
+                app.inhibit_applied.remove(&addr);
+            }
+            Ok(_) => {}
+            Err(error) => {
+                log_error(app, Some(&addr), error);
+            }
+        },
+        Message::ControllerActivity(addr) => {
+            if app.connected.iter().any(|dev| dev.addr == addr) {
+                app.last_activity.insert(addr, Instant::now());
+            }
+        }
+        Message::ActivityMonitorFailed(addr, error) => {
+            app.last_activity.remove(&addr);
+            log_error(app, Some(&addr), error);
+        }
+        Message::IdleInhibited(result) => match result {
+            Ok(inhibitor) => {
+                app.idle_inhibit_pending = false;
+                app.idle_inhibit_retry = None;
+                app.idle_inhibitor = Some(inhibitor);
+                return sync_idle_inhibitor(app);
+            }
+            Err(error) => {
+                // The inhibit service may just not be up yet, so try again after a while
+                // instead of every tick.
+                app.idle_inhibit_pending = false;
+                app.idle_inhibit_retry = Some(Instant::now() + IDLE_INHIBIT_RETRY);
+                log_error(app, None, error);
+            }
+        },
+        Message::IdleReleased(result) => {
+            if let Err(error) = result {
+                log_error(app, None, error);
+            }
+        }
+        Message::PrepareForSleep(delay) => {
+            app.connected_before_sleep = app.connected.iter().map(|dev| dev.addr.clone()).collect();
+            if !app.config.disconnect_on_suspend {
+                return Task::none();
+            }
+
+            // The delay lock is held until the last of these disconnects has gone through the
+            // device queue.
+            let addrs = app.connected_before_sleep.clone();
+            app.auto_connect_paused.extend(addrs.iter().cloned());
+            app.sleep_disconnecting = addrs.iter().cloned().collect();
+            app.sleep_delay = (!addrs.is_empty()).then_some(delay).flatten();
+            let tasks: Vec<_> = addrs
+                .into_iter()
+                .map(|addr| enqueue_operation(app, addr, DeviceOp::Disconnect))
+                .collect();
+            return Task::batch(tasks);
+        }
+        Message::Resumed => {
+            app.sleep_disconnecting.clear();
+            app.sleep_delay = None;
+            let addrs = std::mem::take(&mut app.connected_before_sleep);
+            for addr in &addrs {
+                app.auto_connect_paused.remove(addr);
+            }
+            let refresh = refresh_devices(app);
+            if !app.config.reconnect_on_resume || addrs.is_empty() {
+                return refresh;
+            }
+
+            let reconnect = cosmic::task::future(async move {
+                // Give the adapter a moment to power back up after resume.
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                Message::ResumeConnect(addrs)
+            });
+            return refresh.chain(reconnect);
+        }
+        Message::ResumeConnect(addrs) => {
+            let tasks: Vec<_> = addrs
+                .into_iter()
+                .map(|addr| enqueue_operation(app, addr, DeviceOp::Connect))
+                .collect();
+            return Task::batch(tasks);
+        }
+        Message::ResumeConnectResult(addr, result) => {
+            let next = finish_operation(app, &addr);
+            if let Err(error) = result {
+                log_error(app, Some(&addr), error);
+            }
+            return Task::batch([next, refresh_devices(app)]);
+        }
+        Message::SetAppSetting(setting) => {
+            let reload = matches!(
+                setting,
+                AppSetting::DeviceFilter(_) | AppSetting::Adapter(_)
+            );
+            let inhibit = matches!(setting, AppSetting::InhibitScreenBlanking(_));
+            update_config(app, |config| match setting {
+                AppSetting::RefreshInterval(seconds) => config.refresh_interval_secs = seconds,
+                AppSetting::RefreshWhenClosed(enabled) => config.refresh_when_closed = enabled,
+                AppSetting::ReconnectTimeout(seconds) => config.reconnect_timeout_secs = seconds,
+                AppSetting::DiscoveryTimeout(seconds) => config.discovery_timeout_secs = seconds,
+                AppSetting::DiscoveryTransport(transport) => config.discovery_transport = transport,
+                AppSetting::DeviceFilter(filter) => config.device_filter = filter,
+                AppSetting::Adapter(adapter) => config.adapter = adapter,
+                AppSetting::NotifyConnection(enabled) => config.notify_connection = enabled,
+                AppSetting::NotifyBattery(enabled) => config.notify_battery = enabled,
+                AppSetting::BatteryLow(percent) => config.battery_low_percent = percent,
+                AppSetting::BatteryCritical(percent) => config.battery_critical_percent = percent,
+                AppSetting::PanelDisplay(display) => config.panel_display = display,
+                AppSetting::IdleTimeout(minutes) => config.idle_timeout_minutes = minutes,
+                AppSetting::InhibitScreenBlanking(enabled) => {
+                    config.inhibit_screen_blanking = enabled;
+                }
+                AppSetting::DisconnectOnSuspend(enabled) => config.disconnect_on_suspend = enabled,
+                AppSetting::ReconnectOnResume(enabled) => config.reconnect_on_resume = enabled,
+                AppSetting::AutoConnectAttempts(attempts) => {
+                    config.auto_connect_max_attempts = attempts;
+                }
+                AppSetting::AutoRepair(enabled) => config.auto_repair = enabled,
+                AppSetting::LogLevel(level) => config.log_level = level,
+                AppSetting::AnonymizeDiagnostics(enabled) => config.anonymize_diagnostics = enabled,
+            });
+
+            if inhibit {
+                app.idle_inhibit_pending = false;
+                app.idle_inhibit_retry = None;
+                return sync_idle_inhibitor(app);
+            }
+            if reload {
+                return refresh_devices(app);
+            }
+        }
+        Message::NotifyResult(result) => {
+            if let Err(error) = result {
+                log_error(app, None, error);
+            }
+        }
+        Message::DevicePresence(PresenceEvent::Advertising(addr)) => {
+            // The paired list leaves out hidden and filtered controllers, so ask BlueZ whether
+            // the bond is still there before treating the advertisement as a lost bond.
+            let paired = app.paired.iter().any(|(known, _)| known == &addr)
+                || app
+                    .bluez
+                    .as_ref()
+                    .and_then(|bluez| get_device_property(bluez, &addr, "Paired"))
+                    .and_then(|paired| bool::try_from(paired).ok())
+                    .unwrap_or(false);
+            if paired {
+                return try_auto_connect(app, addr);
+            }
+            return try_auto_repair(app, addr);
+        }
+        Message::DevicePresence(PresenceEvent::Connected(addr, connected)) => {
+            let was_connected = app.connected.iter().any(|dev| dev.addr == addr);
+            let refresh = refresh_devices(app);
+            if connected {
+                app.auto_connect.remove(&addr);
+                app.auto_connect_paused.remove(&addr);
+                return refresh;
+            }
+            if was_connected {
+                return refresh;
+            }
+
+            // The controller paged us but never finished connecting its profiles.
+            return Task::batch([refresh, try_auto_connect(app, addr)]);
+        }
+        Message::AutoConnectResult(addr, result) => {
+            let next = finish_operation(app, &addr);
+            match result {
+                Ok(()) => {
+                    app.auto_connect.remove(&addr);
+                    log_event(app, Some(&addr), fl!("history-auto-connected"));
+                    return Task::batch([next, refresh_devices(app)]);
+                }
+                Err(error) => {
+                    let max_attempts = app.config.auto_connect_max_attempts;
+                    let mut exhausted = false;
+                    if let Some(state) = app.auto_connect.get_mut(&addr) {
+                        state.pending = false;
+                        state.next_attempt =
+                            Instant::now() + AUTO_CONNECT_BACKOFF * 2u32.pow(state.attempts.min(6));
+                        exhausted = state.attempts >= max_attempts;
+                    }
+                    if exhausted {
+                        log_error(app, Some(&addr), error);
+                    }
+                    return next;
+                }
+            }
+        }
+        Message::RepairResult(addr, result) => {
+            let next = finish_operation(app, &addr);
+            load_pending_reconnects(app);
+            let name = app
+                .config
+                .device(&addr)
+                .and_then(|settings| settings.alias.clone())
+                .unwrap_or_else(|| addr.clone());
+            let name = app.config.display_name(&addr, &name).to_string();
+
+            return match result {
+                Ok(entry) => {
+                    app.repairing.remove(&addr);
+                    restore_applet_settings(app, entry);
+                    log_event(app, Some(&addr), fl!("history-repaired"));
+                    Task::batch([
+                        next,
+                        notify(fl!("notify-repaired"), name),
+                        refresh_devices(app),
+                    ])
+                }
+                Err(error) => {
+                    let body = fl!(
+                        "notify-repair-failed-body",
+                        name = name,
+                        error = error.clone()
+                    );
+                    log_error(app, Some(&addr), error);
+                    Task::batch([next, notify(fl!("notify-repair-failed"), body)])
+                }
+            };
+        }
+        Message::DismissHistory(id) => {
+            app.history.retain(|entry| entry.id != id);
+        }
+        Message::ClearHistory => {
+            app.history.clear();
+        }
+        Message::CopyHistory(id) => {
+            if let Some(entry) = app.history.iter().find(|entry| entry.id == id) {
+                return cosmic::iced::clipboard::write(history_line(entry));
+            }
+        }
+        Message::ExportDiagnostics(target) => {
+            let history: Vec<String> = app.history.iter().rev().map(history_line).collect();
+            let anonymize = app.config.anonymize_diagnostics;
+            let bluez = app.bluez.clone();
+            return cosmic::task::future(async move {
+                let report = collect_diagnostics(bluez.as_ref(), &history, anonymize).await;
+                Message::DiagnosticsCollected(target, report)
+            });
+        }
+        Message::DiagnosticsCollected(DiagnosticsTarget::Clipboard, report) => {
+            log_event(app, None, fl!("diagnostics-copied"));
+            return cosmic::iced::clipboard::write(report);
+        }
+        Message::DiagnosticsCollected(DiagnosticsTarget::File, report) => {
+            return cosmic::task::future(async move {
+                Message::DiagnosticsSaved(save_diagnostics(report).await)
+            });
+        }
+        Message::CompatibilityLoaded(result) => match result {
+            Ok(reports) => app.compatibility = Some(reports),
+            Err(error) => {
+                app.compatibility = Some(Vec::new());
+                log_error(app, None, error);
+            }
+        },
+        Message::AddController(addr) => {
+            return enqueue_operation(app, addr, DeviceOp::Add);
+        }
+        Message::AddResult(addr, result) => {
+            let next = finish_operation(app, &addr);
+            refresh_nearby(app);
+            if let Err(error) = result {
+                log_error(app, Some(&addr), error);
+                return next;
+            }
+            log_event(app, Some(&addr), fl!("history-added"));
+            return Task::batch([next, refresh_devices(app)]);
+        }
+        Message::NearbyDiscoveryFailed(error) => {
+            log_error(app, None, error);
+        }
+        Message::SixaxisPlugged(addr, name) => {
+            if app.sixaxis_prompts.iter().any(|(known, _)| known == &addr)
+                || app
+                    .config
+                    .device(&addr)
+                    .is_some_and(|settings| settings.sixaxis_dismissed)
+            {
+                return Task::none();
+            }
+            app.sixaxis_unplug.retain(|(known, _)| known != &addr);
+            app.sixaxis_prompts.push((addr, name.clone()));
+            if app.config.notify_connection {
+                return notify(fl!("notify-sixaxis-plugged"), name);
             }
+        }
+        Message::TrustSixaxis(addr) => {
+            return enqueue_operation(app, addr, DeviceOp::Trust);
+        }
+        Message::SixaxisTrustResult(addr, result) => {
+            let next = finish_operation(app, &addr);
+            if let Err(error) = result {
+                log_error(app, Some(&addr), error);
+                return next;
+            }
+            if let Some(index) = app
+                .sixaxis_prompts
+                .iter()
+                .position(|(known, _)| known == &addr)
+            {
+                let prompt = app.sixaxis_prompts.remove(index);
+                app.sixaxis_unplug.push(prompt);
+            }
+            log_event(app, Some(&addr), fl!("history-sixaxis-trusted"));
+            return next;
+        }
+        Message::DismissSixaxis(addr) => {
+            // Ignoring the trust prompt is remembered, so a controller kept untrusted on purpose
+            // isn't asked about again on every launch.
+            if app.sixaxis_prompts.iter().any(|(known, _)| known == &addr) {
+                update_config(app, |config| {
+                    config.device_mut(&addr).sixaxis_dismissed = true
+                });
+            }
+            app.sixaxis_prompts.retain(|(known, _)| known != &addr);
+            app.sixaxis_unplug.retain(|(known, _)| known != &addr);
+        }
+        Message::ConfirmFix(finding) => {
+            app.confirm_fix = finding;
+        }
+        Message::ApplyFix(finding) => {
+            app.confirm_fix = None;
+            let Some(fix) = finding.helper_fix() else {
+                return Task::none();
+            };
+            return cosmic::task::future(async move {
+                let result = apply_fix(fix)
+                    .await
+                    .map_err(|err| format!("Failed to apply fix: {err}"));
+                Message::FixApplied(result)
+            });
+        }
+        Message::FixApplied(result) => {
+            if let Err(error) = result {
+                log_error(app, None, error);
+                return Task::none();
+            }
+            log_event(app, None, fl!("compatibility-fix-applied"));
+            app.restart_needed = true;
+            app.compatibility = None;
+            let bluez = shared_bluez(app);
+            return cosmic::task::future(async move {
+                Message::CompatibilityLoaded(load_compatibility(bluez))
+            });
+        }
+        Message::ConfirmRestart(confirm) => {
+            app.confirm_restart = confirm;
+        }
+        Message::RestartBluetooth => {
+            app.confirm_restart = false;
+            return cosmic::task::future(async move {
+                let result = restart_bluetooth()
+                    .await
+                    .map_err(|err| format!("Failed to restart Bluetooth: {err}"));
+                Message::BluetoothRestarted(result)
+            });
+        }
+        Message::BluetoothRestarted(result) => {
+            if let Err(error) = result {
+                log_error(app, None, error);
+                return Task::none();
+            }
+            log_event(app, None, fl!("compatibility-restarted"));
+            app.restart_needed = false;
+            app.compatibility = None;
+            let bluez = shared_bluez(app);
+            return cosmic::task::future(async move {
+                Message::CompatibilityLoaded(load_compatibility(bluez))
+            });
+        }
+        Message::DiagnosticsSaved(result) => match result {
+            Ok(path) => {
+                let path = path.display().to_string();
+                log_event(app, None, fl!("diagnostics-saved", path = path));
+            }
+            Err(error) => log_error(app, None, error),
         },
         Message::UpdateConfig(config) => {
             app.config = config;
+            logging::set_level(app.config.log_level);
         }
         Message::TogglePopup => {
             return if let Some(p) = app.popup.take() {
@@ -216,109 +1198,830 @@ pub fn update(app: &mut AppModel, message: Message) -> Task<cosmic::Action<Messa
     Task::none()
 }
 
-pub async fn load_devices() -> Result<DeviceData, String> {
-    let conn = Connection::system()
-        .await
-        .map_err(|err| format!("DBus error: {err}"))?;
+fn set_player_slot(app: &mut AppModel, addr: String, slot: u8) -> Task<cosmic::Action<Message>> {
+    if app
+        .connected
+        .iter()
+        .any(|dev| dev.addr == addr && !dev.gamepad)
+    {
+        return Task::none();
+    }
+
+    let mut slots = app.config.player_slots();
+    let current = slots.get(&addr).copied();
+
+    let holder = slots
+        .iter()
+        .find(|(known, used)| **used == slot && **known != addr)
+        .map(|(known, _)| known.clone());
+    if let Some(holder) = holder {
+        match current {
+            Some(current) => slots.insert(holder.clone(), current),
+            None => slots.remove(&holder),
+        };
+        app.player_leds.remove(&holder);
+    }
+
+    slots.insert(addr.clone(), slot);
+    app.player_leds.remove(&addr);
+    update_config(app, |config| config.set_player_slots(&slots));
+    sync_player_leds(app)
+}
+
+fn sync_player_leds(app: &mut AppModel) -> Task<cosmic::Action<Message>> {
+    let mut slots = app.config.player_slots();
+    let mut tasks = Vec::new();
+
+    // Slots handed to other devices by older versions go back to the controllers.
+    for dev in app.connected.iter().filter(|dev| !dev.gamepad) {
+        slots.remove(&dev.addr);
+        app.player_leds.remove(&dev.addr);
+    }
+
+    for dev in app.connected.iter().filter(|dev| dev.gamepad) {
+        let slot = match slots.get(&dev.addr) {
+            Some(slot) => *slot,
+            None => {
+                let slot = free_player_slot(&slots, &app.connected);
+                slots.insert(dev.addr.clone(), slot);
+                slot
+            }
+        };
+
+        if app.player_leds.get(&dev.addr) != Some(&slot) {
+            app.player_leds.insert(dev.addr.clone(), slot);
+            tasks.push(apply_player_leds(dev.addr.clone(), slot));
+        }
+    }
+
+    update_config(app, |config| config.set_player_slots(&slots));
+
+    Task::batch(tasks)
+}
+
+fn free_player_slot(slots: &HashMap<String, u8>, connected: &[ConnectedDevice]) -> u8 {
+    let unassigned = (1..=PLAYER_SLOTS).find(|slot| !slots.values().any(|used| used == slot));
+    let unused = || {
+        (1..=PLAYER_SLOTS).find(|slot| {
+            !connected
+                .iter()
+                .any(|dev| dev.gamepad && slots.get(&dev.addr) == Some(slot))
+        })
+    };
+
+    unassigned.or_else(unused).unwrap_or(1)
+}
+
+fn update_config(app: &mut AppModel, update: impl FnOnce(&mut Config)) {
+    let mut config = app.config.clone();
+    update(&mut config);
+    if config == app.config {
+        return;
+    }
+
+    app.config = config;
+    logging::set_level(app.config.log_level);
+    if let Some(handler) = &app.config_handler
+        && let Err(err) = app.config.write_entry(handler)
+    {
+        log_error(app, None, format!("Failed to save config: {err}"));
+    }
+}
+
+fn sync_input_inhibit(app: &mut AppModel) -> Task<cosmic::Action<Message>> {
+    let mut tasks = Vec::new();
+
+    for dev in &app.connected {
+        if app.inhibit_applied.contains(&dev.addr) {
+            continue;
+        }
+
+        let kinds: Vec<InputNodeKind> = [InputNodeKind::Touchpad, InputNodeKind::MotionSensors]
+            .into_iter()
+            .filter(|kind| app.config.input_inhibited(&dev.addr, *kind))
+            .collect();
+        if kinds.is_empty() {
+            continue;
+        }
+
+        app.inhibit_applied.insert(dev.addr.clone());
+        tasks.push(apply_input_inhibit(dev.addr.clone(), kinds, true));
+    }
+
+    Task::batch(tasks)
+}
+
+fn apply_input_inhibit(
+    addr: String,
+    kinds: Vec<InputNodeKind>,
+    inhibited: bool,
+) -> Task<cosmic::Action<Message>> {
+    cosmic::task::future(async move {
+        let result = kinds
+            .into_iter()
+            .try_fold(0, |count, kind| {
+                set_input_inhibited(&addr, kind, inhibited).map(|nodes| count + nodes)
+            })
+            .map_err(|err| format!("Failed to change input node: {err}"));
+        Message::InputInhibitResult(addr, result)
+    })
+}
+
+fn sync_idle_inhibitor(app: &mut AppModel) -> Task<cosmic::Action<Message>> {
+    let window = Duration::from_secs(u64::from(app.config.screen_inhibit_minutes) * 60);
+    let active = app.config.inhibit_screen_blanking
+        && app
+            .last_activity
+            .values()
+            .any(|last| last.elapsed() < window);
+
+    // A new play session gets a fresh attempt.
+    if !active {
+        app.idle_inhibit_retry = None;
+    }
+    let waiting = app
+        .idle_inhibit_retry
+        .is_some_and(|retry| Instant::now() < retry);
+
+    if active && app.idle_inhibitor.is_none() && !app.idle_inhibit_pending && !waiting {
+        app.idle_inhibit_pending = true;
+        return cosmic::task::future(async {
+            let result = inhibit_idle(&fl!("inhibit-reason"))
+                .await
+                .map(Arc::new)
+                .map_err(|err| format!("Failed to inhibit screen blanking: {err}"));
+            Message::IdleInhibited(result)
+        });
+    }
+
+    if !active && let Some(inhibitor) = app.idle_inhibitor.take() {
+        return release_idle_inhibitor(inhibitor);
+    }
+
+    Task::none()
+}
+
+fn release_idle_inhibitor(inhibitor: Arc<IdleInhibitor>) -> Task<cosmic::Action<Message>> {
+    cosmic::task::future(async move {
+        let result = inhibitor
+            .release()
+            .await
+            .map_err(|err| format!("Failed to release screen blanking inhibitor: {err}"));
+        Message::IdleReleased(result)
+    })
+}
+
+fn try_auto_connect(app: &mut AppModel, addr: String) -> Task<cosmic::Action<Message>> {
+    if !app.bluez_available
+        || !app.config.auto_connects(&addr)
+        || app.auto_connect_paused.contains(&addr)
+        || app.reconnecting.contains_key(&addr)
+        || app.operations.contains_key(&addr)
+        || app.connected.iter().any(|dev| dev.addr == addr)
+    {
+        return Task::none();
+    }
+
+    let max_attempts = app.config.auto_connect_max_attempts;
+    let state = app
+        .auto_connect
+        .entry(addr.clone())
+        .or_insert_with(|| AutoConnectState {
+            attempts: 0,
+            next_attempt: Instant::now(),
+            last_seen: Instant::now(),
+            pending: false,
+        });
+
+    // A controller that wasn't seen for a few scan windows went out of range, so it gets a fresh
+    // set of attempts when it comes back.
+    if !state.pending && state.last_seen.elapsed() >= AUTO_CONNECT_OUT_OF_RANGE {
+        state.attempts = 0;
+        state.next_attempt = Instant::now();
+    }
+    state.last_seen = Instant::now();
+
+    if state.pending || state.attempts >= max_attempts || Instant::now() < state.next_attempt {
+        return Task::none();
+    }
 
-    let connected = list_connected_devices(&conn)
+    state.pending = true;
+    state.attempts += 1;
+    enqueue_operation(app, addr, DeviceOp::AutoConnect)
+}
+
+fn enqueue_operation(
+    app: &mut AppModel,
+    addr: String,
+    op: DeviceOp,
+) -> Task<cosmic::Action<Message>> {
+    let queue = app.operations.entry(addr.clone()).or_default();
+    if queue.iter().any(|queued| queued.same_kind(&op)) {
+        return Task::none();
+    }
+
+    queue.push_back(op);
+    if queue.len() > 1 {
+        return Task::none();
+    }
+    start_operation(app, addr)
+}
+
+fn finish_operation(app: &mut AppModel, addr: &str) -> Task<cosmic::Action<Message>> {
+    if let Some(queue) = app.operations.get_mut(addr) {
+        queue.pop_front();
+        if queue.is_empty() {
+            app.operations.remove(addr);
+        }
+    }
+    start_operation(app, addr.to_string())
+}
+
+fn start_operation(app: &mut AppModel, addr: String) -> Task<cosmic::Action<Message>> {
+    let Some(op) = app
+        .operations
+        .get(&addr)
+        .and_then(|queue| queue.front())
+        .cloned()
+    else {
+        return Task::none();
+    };
+    let Some(bluez) = shared_bluez(app) else {
+        // Picked up again once bluetoothd is back on the bus.
+        app.stalled_operations.insert(addr);
+        return Task::none();
+    };
+    tracing::debug!(addr, ?op, "starting device operation");
+
+    match op {
+        DeviceOp::Disconnect => cosmic::task::future(async move {
+            let result = disconnect_by_addr(bluez, addr.clone()).await;
+            Message::DisconnectResult(addr, result)
+        }),
+        DeviceOp::Rename(name) => cosmic::task::future(async move {
+            let result = rename_by_addr(bluez, addr.clone(), name).await;
+            Message::RenameResult(addr, result)
+        }),
+        DeviceOp::Remove => cosmic::task::future(async move {
+            let result = remove_by_addr(bluez, addr.clone()).await;
+            Message::RemoveResult(addr, result)
+        }),
+        DeviceOp::Reconnect(entry) => start_reconnect(app, bluez, entry),
+        DeviceOp::Repair(entry) => cosmic::task::future(async move {
+            let result = repair_by_addr(bluez, entry).await;
+            Message::RepairResult(addr, result)
+        }),
+        DeviceOp::Connect => cosmic::task::future(async move {
+            let result = connect_by_addr(bluez, addr.clone()).await;
+            Message::ResumeConnectResult(addr, result)
+        }),
+        DeviceOp::AutoConnect => cosmic::task::future(async move {
+            let result = connect_by_addr(bluez, addr.clone()).await;
+            Message::AutoConnectResult(addr, result)
+        }),
+        DeviceOp::Add => cosmic::task::future(async move {
+            let result = add_by_addr(bluez, addr.clone()).await;
+            Message::AddResult(addr, result)
+        }),
+        DeviceOp::Trust => cosmic::task::future(async move {
+            let result = trust_by_addr(bluez, addr.clone()).await;
+            Message::SixaxisTrustResult(addr, result)
+        }),
+    }
+}
+
+fn start_reconnect(
+    app: &mut AppModel,
+    bluez: Bluez,
+    entry: JournalEntry,
+) -> Task<cosmic::Action<Message>> {
+    let timeout = app.config.reconnect_timeout();
+    let discovery_timeout = app.config.discovery_timeout().min(timeout);
+    let adapter = app.config.adapter.clone();
+    let filter = DiscoveryFilter {
+        hid_only: detect_model(entry.modalias.as_deref(), &entry.alias) != ControllerModel::Wiimote,
+        ..app.config.discovery_filter()
+    };
+    app.auto_connect_paused.remove(&entry.addr);
+    app.reconnecting
+        .insert(entry.addr.clone(), timeout.as_secs());
+
+    cosmic::task::future(async move {
+        let addr = entry.addr.clone();
+        let result = reconnect_by_addr(bluez, entry, adapter, filter, discovery_timeout).await;
+        Message::ReconnectResult(addr, result)
+    })
+}
+
+fn load_pending_reconnects(app: &mut AppModel) {
+    match journal::load() {
+        Ok(entries) => {
+            app.pending_reconnects = entries
+                .into_iter()
+                .filter(|entry| {
+                    !app.reconnecting.contains_key(&entry.addr)
+                        && !app.repairing.contains_key(&entry.addr)
+                })
+                .collect();
+        }
+        Err(err) => {
+            log_error(
+                app,
+                None,
+                format!("Failed to read reconnect journal: {err}"),
+            );
+        }
+    }
+}
+
+fn try_auto_repair(app: &mut AppModel, addr: String) -> Task<cosmic::Action<Message>> {
+    let Some(settings) = app
+        .config
+        .device(&addr)
+        .filter(|settings| settings.game_controller)
+    else {
+        return Task::none();
+    };
+    if !app.bluez_available
+        || !app.config.auto_repair
+        || app.reconnecting.contains_key(&addr)
+        || app.operations.contains_key(&addr)
+        || app
+            .repairing
+            .get(&addr)
+            .is_some_and(|started| started.elapsed() < REPAIR_COOLDOWN)
+    {
+        return Task::none();
+    }
+
+    let entry = JournalEntry {
+        addr: addr.clone(),
+        alias: settings.alias.clone().unwrap_or_default(),
+        trusted: true,
+        blocked: false,
+        wake_allowed: None,
+        settings: Some(settings.clone()),
+        modalias: cached_modalias(app, &addr),
+        step: ReconnectStep::Pairing,
+    };
+    app.repairing.insert(addr.clone(), Instant::now());
+    enqueue_operation(app, addr, DeviceOp::Repair(entry))
+}
+
+fn restore_applet_settings(app: &mut AppModel, entry: JournalEntry) {
+    // The new bond brings up fresh LED and input nodes, so reapply them on the next refresh.
+    app.player_leds.remove(&entry.addr);
+    app.inhibit_applied.remove(&entry.addr);
+
+    // Settings changed while the re-pair was running are newer than the snapshot, so it only
+    // fills in an entry that went missing.
+    if let Some(settings) = entry.settings
+        && app.config.device(&entry.addr).is_none()
+    {
+        update_config(app, |config| {
+            config.devices.insert(entry.addr, settings);
+        });
+    }
+}
+
+fn notify_connection_changes(
+    app: &AppModel,
+    previous: &[ConnectedDevice],
+) -> Vec<Task<cosmic::Action<Message>>> {
+    if !app.loaded || !app.config.notify_connection {
+        return Vec::new();
+    }
+
+    let connected = app
+        .connected
+        .iter()
+        .filter(|dev| previous.iter().all(|known| known.addr != dev.addr))
+        .map(|dev| (fl!("notify-connected"), dev));
+    let disconnected = previous
+        .iter()
+        .filter(|dev| app.connected.iter().all(|known| known.addr != dev.addr))
+        .map(|dev| (fl!("notify-disconnected"), dev));
+
+    connected
+        .chain(disconnected)
+        .map(|(summary, dev)| {
+            let body = app.config.display_name(&dev.addr, &dev.name).to_string();
+            notify(summary, body)
+        })
+        .collect()
+}
+
+fn notify_battery_levels(app: &mut AppModel) -> Vec<Task<cosmic::Action<Message>>> {
+    let mut tasks = Vec::new();
+    app.battery_warned
+        .retain(|addr, _| app.connected.iter().any(|dev| &dev.addr == addr));
+
+    for dev in &app.connected {
+        let Some(percent) = dev.battery else {
+            continue;
+        };
+        let Some(level) = app.config.battery_level(percent) else {
+            app.battery_warned.remove(&dev.addr);
+            continue;
+        };
+
+        let previous = app.battery_warned.insert(dev.addr.clone(), level);
+        if app.config.notify_battery && previous.is_none_or(|previous| level < previous) {
+            let name = app.config.display_name(&dev.addr, &dev.name).to_string();
+            tasks.push(notify(
+                fl!("notify-battery-low"),
+                fl!("notify-battery-body", name = name, percent = percent),
+            ));
+        }
+    }
+
+    tasks
+}
+
+fn notify(summary: String, body: String) -> Task<cosmic::Action<Message>> {
+    cosmic::task::future(async move {
+        let result = send_notification(&summary, &body)
+            .await
+            .map(|_| ())
+            .map_err(|err| format!("Failed to send notification: {err}"));
+        Message::NotifyResult(result)
+    })
+}
+
+fn history_line(entry: &HistoryEntry) -> String {
+    let time = entry.time.format("%Y-%m-%d %H:%M:%S");
+    match &entry.addr {
+        Some(addr) => format!("[{time}] {addr}: {}", entry.message),
+        None => format!("[{time}] {}", entry.message),
+    }
+}
+
+async fn save_diagnostics(report: String) -> Result<PathBuf, String> {
+    let dir = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .ok_or_else(|| "No download or home directory".to_string())?;
+    let path = dir.join(format!(
+        "{}-diagnostics-{}.txt",
+        env!("CARGO_PKG_NAME"),
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    tokio::fs::write(&path, report)
         .await
-        .map_err(|err| format!("Failed to list connected devices: {err}"))?
+        .map_err(|err| format!("Failed to save diagnostics: {err}"))?;
+    Ok(path)
+}
+
+fn log_error(app: &mut AppModel, addr: Option<&str>, message: impl Into<String>) {
+    push_history(app, HistoryKind::Error, addr, message.into());
+}
+
+fn log_event(app: &mut AppModel, addr: Option<&str>, message: impl Into<String>) {
+    push_history(app, HistoryKind::Event, addr, message.into());
+}
+
+fn push_history(app: &mut AppModel, kind: HistoryKind, addr: Option<&str>, message: String) {
+    let time = Local::now();
+    // Periodic refreshes keep failing the same way while BlueZ is down, so bump the newest
+    // entry instead of filling the history with copies.
+    if let Some(latest) = app.history.front_mut()
+        && latest.kind == kind
+        && latest.addr.as_deref() == addr
+        && latest.message == message
+    {
+        latest.time = time;
+        return;
+    }
+
+    app.next_history_id += 1;
+    app.history.push_front(HistoryEntry {
+        id: app.next_history_id,
+        time,
+        addr: addr.map(str::to_string),
+        kind,
+        message,
+    });
+    app.history.truncate(HISTORY_LIMIT);
+}
+
+fn refresh_devices(app: &mut AppModel) -> Task<cosmic::Action<Message>> {
+    let Some(bluez) = shared_bluez(app) else {
+        return Task::none();
+    };
+    app.refresh_generation += 1;
+    let generation = app.refresh_generation;
+    let filter = app.config.list_filter();
+    cosmic::task::future(
+        async move { Message::DataLoaded(generation, load_devices(&bluez, &filter)) },
+    )
+}
+
+fn resume_operations(app: &mut AppModel) -> Task<cosmic::Action<Message>> {
+    let mut tasks = vec![refresh_devices(app)];
+    for addr in std::mem::take(&mut app.stalled_operations) {
+        tasks.push(start_operation(app, addr));
+    }
+    Task::batch(tasks)
+}
+
+// The shared connection, as long as BlueZ is on the bus to answer it.
+fn shared_bluez(app: &AppModel) -> Option<Bluez> {
+    app.bluez.clone().filter(Bluez::is_available)
+}
+
+// Controllers that are being added stay listed until pairing has finished, even though BlueZ
+// stops reporting them as nearby as soon as they are paired.
+fn refresh_nearby(app: &mut AppModel) {
+    let mut nearby = match shared_bluez(app) {
+        Some(bluez) => list_nearby_gamepads(&bluez, app.config.adapter.as_deref()),
+        None => Vec::new(),
+    };
+    for dev in &app.nearby {
+        if app.operations.contains_key(&dev.addr) && nearby.iter().all(|n| n.addr != dev.addr) {
+            nearby.push(dev.clone());
+        }
+    }
+    app.nearby = nearby;
+}
+
+// Remembered with reconnects so the pairing instructions still match the controller once BlueZ
+// has forgotten it.
+fn cached_modalias(app: &AppModel, addr: &str) -> Option<String> {
+    let bluez = app.bluez.as_ref()?;
+    get_device_property(bluez, addr, "Modalias").and_then(|value| String::try_from(value).ok())
+}
+
+fn apply_player_leds(addr: String, slot: u8) -> Task<cosmic::Action<Message>> {
+    cosmic::task::future(async move {
+        let result =
+            set_player_leds(&addr, slot).map_err(|err| format!("Failed to set player LEDs: {err}"));
+        Message::PlayerLedsResult(addr, slot, result)
+    })
+}
+
+fn load_devices(bluez: &Bluez, filter: &ListFilter) -> DeviceData {
+    let connected = list_connected_devices(bluez, filter)
         .into_iter()
         .map(|(addr, name)| ConnectedDevice {
             battery: read_ps_controller_battery(&addr.to_lowercase())
                 .ok()
                 .flatten()
                 .map(|(_dev, capacity)| capacity),
+            gamepad: is_game_controller(bluez, &addr),
             addr,
             name,
         })
         .collect();
 
-    let paired = list_paired_devices(&conn)
-        .await
-        .map_err(|err| format!("Failed to list paired devices: {err}"))?;
+    DeviceData {
+        connected,
+        paired: list_paired_devices(bluez, filter),
+        bonded: list_paired_devices(bluez, &ListFilter::default())
+            .into_iter()
+            .map(|(addr, _name)| addr)
+            .collect(),
+        adapters: list_adapters(bluez),
+    }
+}
 
-    Ok(DeviceData { connected, paired })
+fn load_compatibility(bluez: Option<Bluez>) -> Result<Vec<ControllerReport>, String> {
+    let bluez = bluez.ok_or_else(|| fl!("bluetooth-unavailable"))?;
+    Ok(check_compatibility(&bluez))
 }
 
-async fn disconnect_by_addr(addr: String) -> Result<(), String> {
-    let conn = Connection::system()
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %addr, op = "disconnect", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn disconnect_by_addr(bluez: Bluez, addr: String) -> Result<(), String> {
+    disconnect_device(&bluez, &addr)
         .await
-        .map_err(|err| format!("DBus error: {err}"))?;
+        .map_err(|err| bluez_failure("Failed to disconnect device", err))?
+        .then_some(())
+        .ok_or_else(|| "Device not found".to_string())
+}
 
-    disconnect_device(&conn, &addr)
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %addr, op = "connect", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn connect_by_addr(bluez: Bluez, addr: String) -> Result<(), String> {
+    connect_device(&bluez, &addr)
         .await
-        .map_err(|err| format!("Failed to disconnect device: {err}"))?
+        .map_err(|err| bluez_failure("Failed to connect device", err))?
         .then_some(())
         .ok_or_else(|| "Device not found".to_string())
 }
 
-async fn remove_by_addr(addr: String) -> Result<(), String> {
-    let conn = Connection::system()
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %addr, op = "remove", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn remove_by_addr(bluez: Bluez, addr: String) -> Result<(), String> {
+    remove_device(&bluez, &addr)
         .await
-        .map_err(|err| format!("DBus error: {err}"))?;
+        .map_err(|err| bluez_failure("Failed to remove device", err))?
+        .then_some(())
+        .ok_or_else(|| "Device not found".to_string())
+}
 
-    remove_device(&conn, &addr)
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %addr, op = "rename", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn rename_by_addr(bluez: Bluez, addr: String, new_alias: String) -> Result<(), String> {
+    rename_paired_device(&bluez, &addr, &new_alias)
         .await
-        .map_err(|err| format!("Failed to remove device: {err}"))?
+        .map_err(|err| bluez_failure("Failed to rename device", err))?
         .then_some(())
         .ok_or_else(|| "Device not found".to_string())
 }
 
-async fn rename_by_addr(addr: String, new_alias: String) -> Result<(), String> {
-    let conn = Connection::system()
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %addr, op = "trust", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn trust_by_addr(bluez: Bluez, addr: String) -> Result<(), String> {
+    trust_device(&bluez, &addr, true)
         .await
-        .map_err(|err| format!("DBus error: {err}"))?;
+        .map_err(|err| bluez_failure("Failed to trust device", err))?
+        .then_some(())
+        .ok_or_else(|| "Device not found".to_string())
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %addr, op = "add", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn add_by_addr(bluez: Bluez, addr: String) -> Result<(), String> {
+    let paired = get_device_property(&bluez, &addr, "Paired")
+        .and_then(|paired| bool::try_from(paired).ok())
+        .unwrap_or(false);
 
-    rename_paired_device(&conn, &addr, &new_alias)
+    if !paired
+        && !pair_device(&bluez, &addr)
+            .await
+            .map_err(|err| bluez_failure("Failed to pair device", err))?
+    {
+        return Err("Device not found".to_string());
+    }
+    trust_device(&bluez, &addr, true)
+        .await
+        .map_err(|err| bluez_failure("Failed to trust device", err))?;
+    connect_device(&bluez, &addr)
         .await
-        .map_err(|err| format!("Failed to rename device: {err}"))?
+        .map_err(|err| bluez_failure("Failed to connect device", err))?
         .then_some(())
         .ok_or_else(|| "Device not found".to_string())
 }
 
-async fn reconnect_by_addr(addr: String, name: String) -> Result<(String, bool), String> {
-    let conn = Connection::system()
-        .await
-        .map_err(|err| format!("DBus error: {err}"))?;
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %entry.addr, op = "reconnect", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn reconnect_by_addr(
+    bluez: Bluez,
+    mut entry: JournalEntry,
+    adapter: Option<String>,
+    filter: DiscoveryFilter,
+    discovery_timeout: Duration,
+) -> Result<JournalEntry, String> {
+    if entry.step <= ReconnectStep::Removing {
+        if let Some(state) = read_device_state(&bluez, &entry.addr) {
+            entry.alias = state.alias.unwrap_or(entry.alias);
+            entry.trusted = state.trusted;
+            entry.blocked = state.blocked;
+            entry.wake_allowed = state.wake_allowed;
+            entry.modalias = state.modalias.or(entry.modalias);
+        }
+        advance(&mut entry, ReconnectStep::Removing)?;
 
-    remove_device(&conn, &addr)
-        .await
-        .map_err(|err| format!("Failed to remove device: {err}"))?;
+        remove_device(&bluez, &entry.addr)
+            .await
+            .map_err(|err| bluez_failure("Failed to remove device", err))?;
+        advance(&mut entry, ReconnectStep::Discovering)?;
+    }
 
-    let found = discover_device_by_address(&conn, &addr, Duration::from_secs(60))
+    if entry.step <= ReconnectStep::Discovering {
+        let discovery = discover_device_by_address(
+            &bluez,
+            &entry.addr,
+            adapter.as_deref(),
+            &filter,
+            discovery_timeout,
+        )
         .await
-        .map_err(|err| format!("Failed to discover device: {err}"))?;
+        .map_err(|err| bluez_failure("Failed to discover device", err))?;
 
-    if !found {
-        return Err(fl!("reconnect-not-found").to_string());
+        if !discovery.found {
+            return Err(if discovery.shared {
+                fl!("reconnect-not-found-shared")
+            } else {
+                fl!("reconnect-not-found")
+            });
+        }
+        advance(&mut entry, ReconnectStep::Pairing)?;
     }
 
-    if !pair_device(&conn, &addr)
-        .await
-        .map_err(|err| format!("Failed to pair device: {err}"))?
-    {
-        return Err(fl!("reconnect-pair-failed").to_string());
+    pair_trust_connect(&bluez, &mut entry).await?;
+    Ok(entry)
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(addr = %entry.addr, op = "repair", bluez_error = tracing::field::Empty),
+    err
+)]
+async fn repair_by_addr(bluez: Bluez, mut entry: JournalEntry) -> Result<JournalEntry, String> {
+    if let Err(error) = pair_trust_connect(&bluez, &mut entry).await {
+        // The bond was already gone before we started, so there is nothing to resume.
+        let _ = journal::finish(&entry.addr);
+        return Err(error);
     }
+    Ok(entry)
+}
 
-    if !trust_device(&conn, &addr, true)
-        .await
-        .map_err(|err| format!("Failed to trust device: {err}"))?
-    {
-        return Err(fl!("reconnect-trust-failed").to_string());
+async fn pair_trust_connect(bluez: &Bluez, entry: &mut JournalEntry) -> Result<(), String> {
+    let addr = entry.addr.clone();
+
+    if entry.step <= ReconnectStep::Pairing {
+        let paired = get_device_property(bluez, &addr, "Paired")
+            .and_then(|paired| bool::try_from(paired).ok())
+            .unwrap_or(false);
+
+        if !paired
+            && !pair_device(bluez, &addr)
+                .await
+                .map_err(|err| bluez_failure("Failed to pair device", err))?
+        {
+            return Err(fl!("reconnect-pair-failed").to_string());
+        }
+        advance(entry, ReconnectStep::Trusting)?;
     }
 
-    if !connect_device(&conn, &addr)
-        .await
-        .map_err(|err| format!("Failed to connect device: {err}"))?
-    {
-        return Err(fl!("reconnect-connect-failed").to_string());
+    if entry.step <= ReconnectStep::Trusting {
+        if !trust_device(bluez, &addr, entry.trusted)
+            .await
+            .map_err(|err| bluez_failure("Failed to trust device", err))?
+        {
+            return Err(fl!("reconnect-trust-failed").to_string());
+        }
+        advance(entry, ReconnectStep::Connecting)?;
+    }
+
+    if entry.step <= ReconnectStep::Connecting {
+        if !connect_device(bluez, &addr)
+            .await
+            .map_err(|err| bluez_failure("Failed to connect device", err))?
+        {
+            return Err(fl!("reconnect-connect-failed").to_string());
+        }
+        advance(entry, ReconnectStep::Restoring)?;
+    }
+
+    if !entry.alias.is_empty() {
+        let result = rename_paired_device(bluez, &addr, &entry.alias).await;
+        log_restore("Alias", result);
+    }
+    if let Some(wake_allowed) = entry.wake_allowed {
+        let result =
+            set_device_property(bluez, &addr, "WakeAllowed", Value::from(wake_allowed)).await;
+        log_restore("WakeAllowed", result);
     }
+    // Blocking drops the connection, so restore it last.
+    if entry.blocked {
+        let result = set_device_property(bluez, &addr, "Blocked", Value::from(true)).await;
+        log_restore("Blocked", result);
+    }
+
+    journal::finish(&addr).map_err(|err| format!("Failed to update reconnect journal: {err}"))
+}
 
-    if !name.is_empty() {
-        let _ = rename_paired_device(&conn, &addr, &name).await;
+// Restoring these is best effort, since the controller is already connected again.
+fn log_restore(property: &str, result: zbus::Result<bool>) {
+    match result {
+        Ok(true) => {}
+        Ok(false) => tracing::warn!("failed to restore {property}: device missing or not paired"),
+        Err(err) => tracing::warn!("failed to restore {property}: {err}"),
     }
+}
+
+fn bluez_failure(context: &str, err: zbus::Error) -> String {
+    if let zbus::Error::MethodError(name, _, _) = &err {
+        tracing::Span::current().record("bluez_error", name.as_str());
+    }
+    format!("{context}: {err}")
+}
 
-    Ok((addr, true))
+fn advance(entry: &mut JournalEntry, step: ReconnectStep) -> Result<(), String> {
+    tracing::debug!(?step, "reconnect step");
+    entry.step = step;
+    journal::record(entry).map_err(|err| format!("Failed to write reconnect journal: {err}"))
 }
@@ -1,3 +1,4 @@
+use crate::services::ControllerModel;
 use cosmic::widget;
 
 const DISCONNECT_SVG: &[u8] =
@@ -6,6 +7,15 @@ const RENAME_SVG: &[u8] = include_bytes!("../../resources/icons/edit-symbolic.sv
 const FULLY_RECONNECT_SVG: &[u8] =
     include_bytes!("../../resources/icons/view-refresh-symbolic.svg");
 const REMOVE_SVG: &[u8] = include_bytes!("../../resources/icons/user-trash-symbolic.svg");
+const OPTIONS_SVG: &[u8] = include_bytes!("../../resources/icons/emblem-system-symbolic.svg");
+const COPY_SVG: &[u8] = include_bytes!("../../resources/icons/edit-copy-symbolic.svg");
+const DISMISS_SVG: &[u8] = include_bytes!("../../resources/icons/window-close-symbolic.svg");
+const PAIRING_PLAYSTATION_SVG: &[u8] = include_bytes!("../../resources/pairing/playstation.svg");
+const PAIRING_XBOX_SVG: &[u8] = include_bytes!("../../resources/pairing/xbox.svg");
+const PAIRING_NINTENDO_SVG: &[u8] = include_bytes!("../../resources/pairing/nintendo.svg");
+const PAIRING_8BITDO_SVG: &[u8] = include_bytes!("../../resources/pairing/8bitdo.svg");
+const PAIRING_WIIMOTE_SVG: &[u8] = include_bytes!("../../resources/pairing/wiimote.svg");
+const PAIRING_GENERIC_SVG: &[u8] = include_bytes!("../../resources/pairing/generic.svg");
 
 fn svg_icon(bytes: &'static [u8]) -> widget::icon::Handle {
     let mut svg = String::from_utf8_lossy(bytes).into_owned();
@@ -32,3 +42,30 @@ pub fn reconnect_icon() -> widget::icon::Handle {
 pub fn remove_icon() -> widget::icon::Handle {
     svg_icon(REMOVE_SVG)
 }
+
+pub fn options_icon() -> widget::icon::Handle {
+    svg_icon(OPTIONS_SVG)
+}
+
+pub fn copy_icon() -> widget::icon::Handle {
+    svg_icon(COPY_SVG)
+}
+
+pub fn dismiss_icon() -> widget::icon::Handle {
+    svg_icon(DISMISS_SVG)
+}
+
+// Full color drawings, so unlike the symbolic icons above they are used as they are.
+pub fn pairing_illustration(model: ControllerModel) -> widget::icon::Handle {
+    let bytes = match model {
+        ControllerModel::DualShock3 | ControllerModel::DualShock4 | ControllerModel::DualSense => {
+            PAIRING_PLAYSTATION_SVG
+        }
+        ControllerModel::Xbox => PAIRING_XBOX_SVG,
+        ControllerModel::SwitchPro | ControllerModel::JoyCon => PAIRING_NINTENDO_SVG,
+        ControllerModel::EightBitDo => PAIRING_8BITDO_SVG,
+        ControllerModel::Wiimote => PAIRING_WIIMOTE_SVG,
+        ControllerModel::Other => PAIRING_GENERIC_SVG,
+    };
+    widget::icon::from_svg_bytes(bytes)
+}
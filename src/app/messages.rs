@@ -1,5 +1,50 @@
-use crate::config::Config;
+use crate::config::{ColorTag, Config, DeviceFilter, DiscoveryTransport, LogLevel, PanelDisplay};
+use crate::journal::JournalEntry;
+use crate::services::{
+    Bluez, ControllerReport, Finding, IdleInhibitor, InputNodeKind, PresenceEvent, SleepDelay,
+};
 use cosmic::iced::window::Id;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum DeviceSetting {
+    Color(Option<ColorTag>),
+    Hidden(bool),
+    Favorite(bool),
+    AutoConnect(bool),
+    IdleTimeout(Option<u32>),
+}
+
+#[derive(Debug, Clone)]
+pub enum AppSetting {
+    RefreshInterval(u32),
+    RefreshWhenClosed(bool),
+    ReconnectTimeout(u32),
+    DiscoveryTimeout(u32),
+    DiscoveryTransport(DiscoveryTransport),
+    DeviceFilter(DeviceFilter),
+    Adapter(Option<String>),
+    NotifyConnection(bool),
+    NotifyBattery(bool),
+    BatteryLow(u8),
+    BatteryCritical(u8),
+    PanelDisplay(PanelDisplay),
+    IdleTimeout(u32),
+    InhibitScreenBlanking(bool),
+    DisconnectOnSuspend(bool),
+    ReconnectOnResume(bool),
+    AutoConnectAttempts(u32),
+    AutoRepair(bool),
+    LogLevel(LogLevel),
+    AnonymizeDiagnostics(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DiagnosticsTarget {
+    Clipboard,
+    File,
+}
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -7,17 +52,68 @@ pub enum Message {
     PopupClosed(Id),
     Refresh,
     Tick,
-    DataLoaded(Result<super::DeviceData, String>),
+    SpinnerTick,
+    DataLoaded(u64, super::DeviceData),
     DisconnectDevice(String),
-    DisconnectResult(Result<(), String>),
+    DisconnectResult(String, Result<(), String>),
     RenameStart(String, String),
     RenameInput(String),
     RenameCancel,
     RenameSubmit(String),
-    RenameResult(Result<(), String>),
+    RenameResult(String, Result<(), String>),
     RemoveDevice(String),
-    RemoveResult(Result<(), String>),
+    RemoveResult(String, Result<(), String>),
     ReconnectDevice(String),
-    ReconnectResult(Result<(String, bool), String>),
+    ReconnectResult(String, Result<JournalEntry, String>),
+    ResumeReconnect(String),
+    DiscardReconnect(String),
+    CyclePlayerSlot(String),
+    SetPlayerSlot(String, u8),
+    PlayerLedsResult(String, u8, Result<bool, String>),
+    OpenPage(super::Page),
+    Back,
+    SetShowHidden(bool),
+    NicknameInput(String),
+    NicknameSubmit(String),
+    SetDeviceSetting(String, DeviceSetting),
+    ForgetDevice(String),
+    InputNodesLoaded(String, Result<Vec<InputNodeKind>, String>),
+    SetInputInhibited(String, InputNodeKind, bool),
+    InputInhibitResult(String, Result<usize, String>),
+    ControllerActivity(String),
+    ActivityMonitorFailed(String, String),
+    IdleInhibited(Result<Arc<IdleInhibitor>, String>),
+    IdleReleased(Result<(), String>),
+    PrepareForSleep(Option<Arc<SleepDelay>>),
+    Resumed,
+    ResumeConnect(Vec<String>),
+    ResumeConnectResult(String, Result<(), String>),
+    SetAppSetting(AppSetting),
+    NotifyResult(Result<(), String>),
+    DevicePresence(PresenceEvent),
+    BluezConnected(Bluez),
+    BluezAvailability(bool),
+    AutoConnectResult(String, Result<(), String>),
+    RepairResult(String, Result<JournalEntry, String>),
+    DismissHistory(u64),
+    ClearHistory,
+    CopyHistory(u64),
+    ExportDiagnostics(DiagnosticsTarget),
+    DiagnosticsCollected(DiagnosticsTarget, String),
+    DiagnosticsSaved(Result<PathBuf, String>),
+    CompatibilityLoaded(Result<Vec<ControllerReport>, String>),
+    AddController(String),
+    AddResult(String, Result<(), String>),
+    NearbyDiscoveryFailed(String),
+    SixaxisPlugged(String, String),
+    TrustSixaxis(String),
+    SixaxisTrustResult(String, Result<(), String>),
+    DismissSixaxis(String),
+    ConfirmFix(Option<Finding>),
+    ApplyFix(Finding),
+    FixApplied(Result<(), String>),
+    ConfirmRestart(bool),
+    RestartBluetooth,
+    BluetoothRestarted(Result<(), String>),
     UpdateConfig(Config),
 }
@@ -6,8 +6,11 @@ mod view;
 
 pub const APP_ID: &str = "com.keewee.CosmicBluetoothGamepad";
 
-pub use messages::Message;
-pub use model::{AppModel, ConnectedDevice, DeviceData};
+pub use messages::{AppSetting, DeviceSetting, DiagnosticsTarget, Message};
+pub use model::{
+    AppModel, AutoConnectState, ConnectedDevice, DeviceData, DeviceOp, HistoryEntry, HistoryKind,
+    Page,
+};
 
 use cosmic::iced::window::Id;
 use cosmic::prelude::*;
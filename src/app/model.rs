@@ -1,18 +1,82 @@
 use crate::config::Config;
+use crate::journal::JournalEntry;
+use crate::services::{
+    Bluez, ControllerReport, Finding, IdleInhibitor, InputNodeKind, NearbyDevice, SleepDelay,
+};
+use chrono::{DateTime, Local};
+use cosmic::cosmic_config;
 use cosmic::iced::window::Id;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
 
 #[derive(Clone, Debug)]
 pub struct ConnectedDevice {
     pub addr: String,
     pub name: String,
     pub battery: Option<u8>,
+    pub gamepad: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct DeviceData {
     pub connected: Vec<ConnectedDevice>,
     pub paired: Vec<(String, String)>,
+    pub bonded: Vec<String>,
+    pub adapters: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AutoConnectState {
+    pub attempts: u32,
+    pub next_attempt: Instant,
+    pub last_seen: Instant,
+    pub pending: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum DeviceOp {
+    Disconnect,
+    Rename(String),
+    Remove,
+    Reconnect(JournalEntry),
+    Repair(JournalEntry),
+    Connect,
+    AutoConnect,
+    Add,
+    Trust,
+}
+
+impl DeviceOp {
+    pub fn same_kind(&self, other: &DeviceOp) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryKind {
+    Error,
+    Event,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub time: DateTime<Local>,
+    pub addr: Option<String>,
+    pub kind: HistoryKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Page {
+    #[default]
+    Main,
+    Device(String),
+    KnownDevices,
+    Settings,
+    Compatibility,
+    AddController,
 }
 
 #[derive(Default)]
@@ -20,11 +84,60 @@ pub struct AppModel {
     pub core: cosmic::Core,
     pub popup: Option<Id>,
     pub config: Config,
+    pub config_handler: Option<cosmic_config::Config>,
     pub connected: Vec<ConnectedDevice>,
     pub paired: Vec<(String, String)>,
     pub paired_names: HashMap<String, String>,
+    pub bonded: Vec<String>,
+    pub adapters: Vec<(String, String)>,
+    pub loaded: bool,
+    pub battery_warned: HashMap<String, u8>,
     pub reconnecting: HashMap<String, u64>,
     pub renaming_addr: Option<String>,
     pub renaming_value: String,
-    pub last_error: Option<String>,
+    pub history: VecDeque<HistoryEntry>,
+    pub next_history_id: u64,
+    pub player_leds: HashMap<String, u8>,
+    pub inhibit_applied: HashSet<String>,
+    pub page: Page,
+    pub page_history: Vec<Page>,
+    pub show_hidden: bool,
+    pub nickname_value: String,
+    pub input_nodes: HashMap<String, Vec<InputNodeKind>>,
+    pub last_activity: HashMap<String, Instant>,
+    pub idle_timeout_options: Vec<String>,
+    pub color_options: Vec<String>,
+    pub player_slot_options: Vec<String>,
+    pub refresh_interval_options: Vec<String>,
+    pub timeout_options: Vec<String>,
+    pub battery_threshold_options: Vec<String>,
+    pub device_filter_options: Vec<String>,
+    pub discovery_transport_options: Vec<String>,
+    pub log_level_options: Vec<String>,
+    pub panel_display_options: Vec<String>,
+    pub adapter_options: Vec<String>,
+    pub idle_inhibitor: Option<Arc<IdleInhibitor>>,
+    pub idle_inhibit_pending: bool,
+    pub idle_inhibit_retry: Option<Instant>,
+    pub connected_before_sleep: Vec<String>,
+    pub sleep_disconnecting: HashSet<String>,
+    pub sleep_delay: Option<Arc<SleepDelay>>,
+    pub auto_connect: HashMap<String, AutoConnectState>,
+    pub auto_connect_paused: HashSet<String>,
+    pub auto_connect_attempt_options: Vec<String>,
+    pub repairing: HashMap<String, Instant>,
+    pub pending_reconnects: Vec<JournalEntry>,
+    pub operations: HashMap<String, VecDeque<DeviceOp>>,
+    pub stalled_operations: HashSet<String>,
+    pub bluez: Option<Bluez>,
+    pub bluez_available: bool,
+    pub compatibility: Option<Vec<ControllerReport>>,
+    pub confirm_fix: Option<Finding>,
+    pub confirm_restart: bool,
+    pub restart_needed: bool,
+    pub nearby: Vec<NearbyDevice>,
+    pub sixaxis_prompts: Vec<(String, String)>,
+    pub sixaxis_unplug: Vec<(String, String)>,
+    pub refresh_generation: u64,
+    pub spinner_frame: usize,
 }
@@ -1,41 +1,136 @@
-use super::{AppModel, Message, icons};
+use super::{
+    AppModel, AppSetting, ConnectedDevice, DeviceOp, DeviceSetting, DiagnosticsTarget, HistoryKind,
+    Message, Page, icons,
+};
+use crate::config::{
+    AUTO_CONNECT_ATTEMPT_PRESETS, BATTERY_THRESHOLD_PRESETS, ColorTag, DeviceFilter,
+    DiscoveryTransport, IDLE_TIMEOUT_PRESETS, LogLevel, PanelDisplay, REFRESH_INTERVAL_PRESETS,
+    TIMEOUT_PRESETS,
+};
 use crate::fl;
+use crate::services::{ControllerModel, Finding, InputNodeKind, detect_model};
 use cosmic::iced::{Alignment, Length, window::Id};
 use cosmic::prelude::*;
 use cosmic::widget;
 
+const SPINNER_FRAMES: [&str; 8] = ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"];
+
+// Shown while searching, grouped by how each family enters pairing mode.
+const PAIRING_GUIDES: [&[ControllerModel]; 5] = [
+    &[ControllerModel::DualSense, ControllerModel::DualShock4],
+    &[ControllerModel::Xbox],
+    &[ControllerModel::SwitchPro, ControllerModel::JoyCon],
+    &[ControllerModel::EightBitDo],
+    &[ControllerModel::Wiimote],
+];
+
 pub fn view(app: &AppModel) -> Element<'_, Message> {
+    let label = match app.config.panel_display {
+        PanelDisplay::Icon => None,
+        PanelDisplay::ConnectedCount => {
+            (!app.connected.is_empty()).then(|| app.connected.len().to_string())
+        }
+        PanelDisplay::LowestBattery => app
+            .connected
+            .iter()
+            .filter_map(|dev| dev.battery)
+            .min()
+            .map(|percent| format!("{percent}%")),
+    };
+
+    let Some(label) = label.filter(|_| app.core.applet.is_horizontal()) else {
+        return app
+            .core
+            .applet
+            .icon_button("applications-games-symbolic")
+            .on_press(Message::TogglePopup)
+            .into();
+    };
+
+    let icon = widget::icon::from_name("applications-games-symbolic")
+        .size(app.core.applet.suggested_size(true).0)
+        .symbolic(true)
+        .icon();
+    let content = widget::row()
+        .align_y(Alignment::Center)
+        .spacing(4)
+        .push(icon)
+        .push(app.core.applet.text(label));
+
     app.core
         .applet
-        .icon_button("applications-games-symbolic")
-        .on_press(Message::TogglePopup)
+        .autosize_window(
+            widget::button::custom(content)
+                .class(cosmic::theme::Button::AppletIcon)
+                .on_press(Message::TogglePopup),
+        )
         .into()
 }
 
 pub fn view_window(app: &AppModel, _id: Id) -> Element<'_, Message> {
+    let mut content = match &app.page {
+        Page::Main => main_page(app),
+        Page::Device(addr) => device_page(app, addr),
+        Page::KnownDevices => known_devices_page(app),
+        Page::Settings => settings_page(app),
+        Page::Compatibility => compatibility_page(app),
+        Page::AddController => add_controller_page(app),
+    };
+
+    if !app.history.is_empty() {
+        content = content.push(history_section(app));
+    }
+
+    app.core.applet.popup_container(content).into()
+}
+
+fn main_page(app: &AppModel) -> widget::Column<'_, Message> {
     let mut connected_list = widget::list_column().padding(5).spacing(0);
-    if app.connected.is_empty() {
+    let connected = ordered(app, &app.connected, |dev: &ConnectedDevice| {
+        dev.addr.as_str()
+    });
+    if connected.is_empty() {
         connected_list = connected_list.add(widget::text(fl!("no-connected")));
     } else {
-        for dev in &app.connected {
+        for dev in connected {
             let battery_text = dev
                 .battery
                 .map(|value| format!("{value}%"))
                 .unwrap_or_else(|| fl!("battery-unknown").to_string());
-            let label = format!("{} ({})", dev.name, dev.addr);
+            let slot_label = match app.config.player_slot(&dev.addr) {
+                Some(slot) => fl!("player-slot", slot = slot),
+                None => fl!("player-slot-none"),
+            };
+            let slot_button = widget::button::text(slot_label)
+                .tooltip(fl!("player-slot-change"))
+                .on_press(Message::CyclePlayerSlot(dev.addr.clone()));
+            let options_button = widget::button::icon(icons::options_icon())
+                .tooltip(fl!("options"))
+                .on_press(Message::OpenPage(Page::Device(dev.addr.clone())))
+                .extra_small();
+            let busy = app.operations.contains_key(&dev.addr);
             let disconnect_button = widget::button::icon(icons::disconnect_icon())
                 .tooltip(fl!("disconnect"))
-                .on_press(Message::DisconnectDevice(dev.addr.clone()))
+                .on_press_maybe((!busy).then(|| Message::DisconnectDevice(dev.addr.clone())))
                 .extra_small();
+            let button_row = widget::row()
+                .spacing(8)
+                .push(options_button)
+                .push(disconnect_button)
+                .width(Length::Shrink);
 
-            let row = widget::row()
+            let mut row = widget::row()
                 .align_y(Alignment::Center)
                 .spacing(8)
-                .push(widget::text(label).width(Length::FillPortion(8)))
+                .push(device_label(app, &dev.addr, &dev.name).width(Length::FillPortion(8)));
+            if dev.gamepad {
+                row = row.push(slot_button);
+            }
+            let row = row
                 .push(widget::text(battery_text).width(Length::FillPortion(2)))
                 .push(
-                    widget::container(disconnect_button)
-                        .width(Length::FillPortion(2))
+                    widget::container(button_row)
+                        .width(Length::FillPortion(3))
                         .align_x(Alignment::End),
                 );
 
@@ -44,10 +139,13 @@ pub fn view_window(app: &AppModel, _id: Id) -> Element<'_, Message> {
     }
 
     let mut paired_list = widget::list_column().padding(5).spacing(0);
-    if app.paired.is_empty() {
+    let paired = ordered(app, &app.paired, |(addr, _): &(String, String)| {
+        addr.as_str()
+    });
+    if paired.is_empty() {
         paired_list = paired_list.add(widget::text(fl!("no-paired")));
     } else {
-        for (addr, name) in &app.paired {
+        for (addr, name) in paired {
             if app.renaming_addr.as_ref() == Some(addr) {
                 let addr_clone = addr.clone();
                 let input = widget::text_input(fl!("rename-placeholder"), &app.renaming_value)
@@ -72,7 +170,6 @@ pub fn view_window(app: &AppModel, _id: Id) -> Element<'_, Message> {
                 continue;
             }
 
-            let label = format!("{} ({})", name, addr);
             let buttons: Element<'_, Message> = if let Some(remaining) = app.reconnecting.get(addr)
             {
                 widget::container(widget::text(fl!("reconnecting", seconds = remaining)))
@@ -80,23 +177,32 @@ pub fn view_window(app: &AppModel, _id: Id) -> Element<'_, Message> {
                     .align_x(Alignment::End)
                     .into()
             } else {
+                let busy = app.operations.contains_key(addr);
+                let options_button = widget::button::icon(icons::options_icon())
+                    .tooltip(fl!("options"))
+                    .on_press(Message::OpenPage(Page::Device(addr.clone())))
+                    .extra_small();
+
                 let rename_button = widget::button::icon(icons::rename_icon())
                     .tooltip(fl!("rename"))
-                    .on_press(Message::RenameStart(addr.clone(), name.clone()))
+                    .on_press_maybe(
+                        (!busy).then(|| Message::RenameStart(addr.clone(), name.clone())),
+                    )
                     .extra_small();
 
                 let refresh_button = widget::button::icon(icons::reconnect_icon())
                     .tooltip(fl!("reconnect"))
-                    .on_press(Message::ReconnectDevice(addr.clone()))
+                    .on_press_maybe((!busy).then(|| Message::ReconnectDevice(addr.clone())))
                     .extra_small();
 
                 let remove_button = widget::button::icon(icons::remove_icon())
                     .tooltip(fl!("remove"))
-                    .on_press(Message::RemoveDevice(addr.clone()))
+                    .on_press_maybe((!busy).then(|| Message::RemoveDevice(addr.clone())))
                     .extra_small();
 
                 let button_row = widget::row()
                     .spacing(8)
+                    .push(options_button)
                     .push(rename_button)
                     .push(refresh_button)
                     .push(remove_button)
@@ -111,24 +217,831 @@ pub fn view_window(app: &AppModel, _id: Id) -> Element<'_, Message> {
             let row = widget::row()
                 .align_y(Alignment::Center)
                 .spacing(8)
-                .push(widget::text(label).width(Length::FillPortion(6)))
+                .push(device_label(app, addr, name).width(Length::FillPortion(6)))
                 .push(buttons);
 
-            paired_list = paired_list.add(row);
+            if let Some(model) = reconnect_model(app, addr) {
+                paired_list = paired_list.add(
+                    widget::column()
+                        .spacing(8)
+                        .push(row)
+                        .push(pairing_guide(model)),
+                );
+            } else {
+                paired_list = paired_list.add(row);
+            }
         }
     }
 
-    let mut content = widget::column()
-        .padding(8)
-        .spacing(8)
+    let mut content = widget::column().padding(8).spacing(8);
+
+    if !app.bluez_available {
+        content = content.push(widget::text::heading(fl!("bluetooth-unavailable")));
+    }
+
+    for entry in &app.pending_reconnects {
+        let name = app.config.display_name(&entry.addr, &entry.alias);
+        let row = widget::row()
+            .align_y(Alignment::Center)
+            .spacing(8)
+            .push(
+                widget::text(fl!(
+                    "reconnect-unfinished",
+                    name = name,
+                    addr = entry.addr.as_str()
+                ))
+                .width(Length::Fill),
+            )
+            .push(
+                widget::button::text(fl!("reconnect-resume")).on_press_maybe(
+                    (!app.operations.contains_key(&entry.addr))
+                        .then(|| Message::ResumeReconnect(entry.addr.clone())),
+                ),
+            )
+            .push(
+                widget::button::text(fl!("reconnect-discard"))
+                    .on_press(Message::DiscardReconnect(entry.addr.clone())),
+            );
+        content = content.push(row);
+    }
+
+    for (addr, name) in &app.sixaxis_prompts {
+        let row = widget::row()
+            .align_y(Alignment::Center)
+            .spacing(8)
+            .push(
+                widget::text(fl!(
+                    "sixaxis-trust-prompt",
+                    name = name.as_str(),
+                    addr = addr.as_str()
+                ))
+                .width(Length::Fill),
+            )
+            .push(widget::button::text(fl!("sixaxis-trust")).on_press_maybe(
+                (!app.operations.contains_key(addr)).then(|| Message::TrustSixaxis(addr.clone())),
+            ))
+            .push(
+                widget::button::text(fl!("sixaxis-ignore"))
+                    .on_press(Message::DismissSixaxis(addr.clone())),
+            );
+        content = content.push(row);
+    }
+
+    for (addr, name) in &app.sixaxis_unplug {
+        let row = widget::row()
+            .align_y(Alignment::Center)
+            .spacing(12)
+            .push(
+                widget::icon(icons::pairing_illustration(ControllerModel::DualShock3))
+                    .width(Length::Fixed(96.0))
+                    .height(Length::Fixed(64.0)),
+            )
+            .push(widget::text(fl!("sixaxis-unplug", name = name.as_str())).width(Length::Fill))
+            .push(
+                widget::button::icon(icons::dismiss_icon())
+                    .tooltip(fl!("history-dismiss"))
+                    .on_press(Message::DismissSixaxis(addr.clone()))
+                    .extra_small(),
+            );
+        content = content.push(row);
+    }
+
+    content = content
         .push(widget::text(fl!("connected-devices")))
         .push(connected_list)
         .push(widget::text(fl!("paired-devices")))
         .push(paired_list);
 
-    if let Some(error) = &app.last_error {
-        content = content.push(widget::text(fl!("error-loading", error = error)));
+    if app.config.devices.values().any(|settings| settings.hidden) {
+        content = content.push(widget::settings::item(
+            fl!("show-hidden"),
+            widget::toggler(app.show_hidden).on_toggle(Message::SetShowHidden),
+        ));
     }
 
-    app.core.applet.popup_container(content).into()
+    content.push(
+        widget::row()
+            .spacing(8)
+            .push(
+                widget::button::text(fl!("add-controller")).on_press_maybe(
+                    app.bluez_available
+                        .then_some(Message::OpenPage(Page::AddController)),
+                ),
+            )
+            .push(
+                widget::button::text(fl!("known-devices"))
+                    .on_press(Message::OpenPage(Page::KnownDevices)),
+            )
+            .push(
+                widget::button::text(fl!("settings")).on_press(Message::OpenPage(Page::Settings)),
+            ),
+    )
+}
+
+fn device_page<'a>(app: &'a AppModel, addr: &'a str) -> widget::Column<'a, Message> {
+    let name = app
+        .connected
+        .iter()
+        .find(|dev| dev.addr == addr)
+        .map(|dev| dev.name.as_str())
+        .or_else(|| app.paired_names.get(addr).map(String::as_str))
+        .unwrap_or(addr);
+    let settings = app.config.device(addr).cloned().unwrap_or_default();
+
+    let nickname_addr = addr.to_string();
+    let nickname = widget::text_input(name, &app.nickname_value)
+        .on_input(Message::NicknameInput)
+        .on_submit(move |_| Message::NicknameSubmit(nickname_addr.clone()));
+    let nickname_row = widget::row()
+        .align_y(Alignment::Center)
+        .spacing(8)
+        .push(nickname.width(Length::Fill))
+        .push(
+            widget::button::text(fl!("save")).on_press(Message::NicknameSubmit(addr.to_string())),
+        );
+
+    let color_addr = addr.to_string();
+    let color_selected = settings
+        .color
+        .and_then(|color| ColorTag::ALL.iter().position(|known| *known == color))
+        .map_or(0, |index| index + 1);
+    let color_dropdown = widget::dropdown(&app.color_options, Some(color_selected), move |index| {
+        let color = index
+            .checked_sub(1)
+            .and_then(|index| ColorTag::ALL.get(index).copied());
+        Message::SetDeviceSetting(color_addr.clone(), DeviceSetting::Color(color))
+    });
+
+    let slot_addr = addr.to_string();
+    let slot_selected = settings.player_slot.map(|slot| usize::from(slot) - 1);
+    let slot_dropdown = widget::dropdown(&app.player_slot_options, slot_selected, move |index| {
+        Message::SetPlayerSlot(slot_addr.clone(), index as u8 + 1)
+    });
+
+    let favorite_addr = addr.to_string();
+    let hidden_addr = addr.to_string();
+    let auto_connect_addr = addr.to_string();
+
+    let mut section = widget::settings::section()
+        .title(format!("{} ({addr})", app.config.display_name(addr, name)))
+        .add(widget::settings::item(fl!("nickname"), nickname_row))
+        .add(widget::settings::item(fl!("color-tag"), color_dropdown))
+        .add(widget::settings::item(
+            fl!("favorite"),
+            widget::toggler(settings.favorite).on_toggle(move |value| {
+                Message::SetDeviceSetting(favorite_addr.clone(), DeviceSetting::Favorite(value))
+            }),
+        ))
+        .add(widget::settings::item(
+            fl!("hidden"),
+            widget::toggler(settings.hidden).on_toggle(move |value| {
+                Message::SetDeviceSetting(hidden_addr.clone(), DeviceSetting::Hidden(value))
+            }),
+        ))
+        .add(widget::settings::item(
+            fl!("auto-connect"),
+            widget::toggler(settings.auto_connect).on_toggle(move |value| {
+                Message::SetDeviceSetting(
+                    auto_connect_addr.clone(),
+                    DeviceSetting::AutoConnect(value),
+                )
+            }),
+        ))
+        .add(widget::settings::item(fl!("player-number"), slot_dropdown));
+
+    let nodes = app.input_nodes.get(addr);
+    for (kind, label) in [
+        (InputNodeKind::Touchpad, fl!("inhibit-touchpad")),
+        (InputNodeKind::MotionSensors, fl!("inhibit-motion-sensors")),
+    ] {
+        if !nodes.is_some_and(|nodes| nodes.contains(&kind)) {
+            continue;
+        }
+
+        let addr = addr.to_string();
+        let toggler = widget::toggler(app.config.input_inhibited(&addr, kind))
+            .on_toggle(move |value| Message::SetInputInhibited(addr.clone(), kind, value));
+        section = section.add(widget::settings::item(label, toggler));
+    }
+
+    let selected = match settings.idle_timeout_minutes {
+        Some(minutes) => IDLE_TIMEOUT_PRESETS
+            .iter()
+            .position(|preset| *preset == minutes)
+            .map(|index| index + 1),
+        None => Some(0),
+    };
+    let idle_addr = addr.to_string();
+    let idle_dropdown = widget::dropdown(&app.idle_timeout_options, selected, move |index| {
+        let minutes = index
+            .checked_sub(1)
+            .and_then(|index| IDLE_TIMEOUT_PRESETS.get(index).copied());
+        Message::SetDeviceSetting(idle_addr.clone(), DeviceSetting::IdleTimeout(minutes))
+    });
+    section = section.add(widget::settings::item(fl!("idle-timeout"), idle_dropdown));
+
+    widget::column()
+        .padding(8)
+        .spacing(8)
+        .push(widget::button::text(fl!("back")).on_press(Message::Back))
+        .push(section)
+        .push(
+            widget::button::destructive(fl!("forget-settings"))
+                .on_press(Message::ForgetDevice(addr.to_string())),
+        )
+}
+
+fn known_devices_page(app: &AppModel) -> widget::Column<'_, Message> {
+    let mut list = widget::list_column().padding(5).spacing(0);
+    if app.config.devices.is_empty() {
+        list = list.add(widget::text(fl!("no-known-devices")));
+    }
+
+    for (addr, settings) in &app.config.devices {
+        let name = app
+            .paired_names
+            .get(addr)
+            .or(settings.alias.as_ref())
+            .map_or(addr.as_str(), String::as_str);
+        let mut flags = Vec::new();
+        if settings.favorite {
+            flags.push(fl!("favorite"));
+        }
+        if settings.hidden {
+            flags.push(fl!("hidden"));
+        }
+
+        let options_button = widget::button::icon(icons::options_icon())
+            .tooltip(fl!("options"))
+            .on_press(Message::OpenPage(Page::Device(addr.clone())))
+            .extra_small();
+        let forget_button = widget::button::icon(icons::remove_icon())
+            .tooltip(fl!("forget-settings"))
+            .on_press(Message::ForgetDevice(addr.clone()))
+            .extra_small();
+
+        let row = widget::row()
+            .align_y(Alignment::Center)
+            .spacing(8)
+            .push(device_label(app, addr, name).width(Length::FillPortion(6)))
+            .push(widget::text(flags.join(", ")).width(Length::FillPortion(2)))
+            .push(
+                widget::container(
+                    widget::row()
+                        .spacing(8)
+                        .push(options_button)
+                        .push(forget_button),
+                )
+                .width(Length::FillPortion(2))
+                .align_x(Alignment::End),
+            );
+
+        list = list.add(row);
+    }
+
+    widget::column()
+        .padding(8)
+        .spacing(8)
+        .push(widget::button::text(fl!("back")).on_press(Message::Back))
+        .push(widget::text(fl!("known-devices")))
+        .push(list)
+}
+
+fn settings_page(app: &AppModel) -> widget::Column<'_, Message> {
+    let config = &app.config;
+
+    let adapter_selected = match &config.adapter {
+        Some(adapter) => app
+            .adapters
+            .iter()
+            .position(|(name, _)| name == adapter)
+            .map(|index| index + 1),
+        None => Some(0),
+    };
+    let adapters: Vec<String> = app.adapters.iter().map(|(name, _)| name.clone()).collect();
+    let adapter_dropdown = widget::dropdown(&app.adapter_options, adapter_selected, move |index| {
+        let adapter = index
+            .checked_sub(1)
+            .and_then(|index| adapters.get(index).cloned());
+        Message::SetAppSetting(AppSetting::Adapter(adapter))
+    });
+
+    let devices = widget::settings::section()
+        .title(fl!("settings-devices"))
+        .add(widget::settings::item(
+            fl!("refresh-interval"),
+            preset_dropdown(
+                &app.refresh_interval_options,
+                &REFRESH_INTERVAL_PRESETS,
+                config.refresh_interval_secs,
+                AppSetting::RefreshInterval,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("refresh-when-closed"),
+            widget::toggler(config.refresh_when_closed)
+                .on_toggle(|value| Message::SetAppSetting(AppSetting::RefreshWhenClosed(value))),
+        ))
+        .add(widget::settings::item(
+            fl!("reconnect-timeout"),
+            preset_dropdown(
+                &app.timeout_options,
+                &TIMEOUT_PRESETS,
+                config.reconnect_timeout_secs,
+                AppSetting::ReconnectTimeout,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("discovery-timeout"),
+            preset_dropdown(
+                &app.timeout_options,
+                &TIMEOUT_PRESETS,
+                config.discovery_timeout_secs,
+                AppSetting::DiscoveryTimeout,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("discovery-transport"),
+            preset_dropdown(
+                &app.discovery_transport_options,
+                &DiscoveryTransport::ALL,
+                config.discovery_transport,
+                AppSetting::DiscoveryTransport,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("auto-connect-attempts"),
+            preset_dropdown(
+                &app.auto_connect_attempt_options,
+                &AUTO_CONNECT_ATTEMPT_PRESETS,
+                config.auto_connect_max_attempts,
+                AppSetting::AutoConnectAttempts,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("auto-repair"),
+            widget::toggler(config.auto_repair)
+                .on_toggle(|value| Message::SetAppSetting(AppSetting::AutoRepair(value))),
+        ))
+        .add(widget::settings::item(
+            fl!("device-filter"),
+            preset_dropdown(
+                &app.device_filter_options,
+                &DeviceFilter::ALL,
+                config.device_filter,
+                AppSetting::DeviceFilter,
+            ),
+        ))
+        .add(widget::settings::item(fl!("adapter"), adapter_dropdown));
+
+    let notifications = widget::settings::section()
+        .title(fl!("settings-notifications"))
+        .add(widget::settings::item(
+            fl!("notify-connection"),
+            widget::toggler(config.notify_connection)
+                .on_toggle(|value| Message::SetAppSetting(AppSetting::NotifyConnection(value))),
+        ))
+        .add(widget::settings::item(
+            fl!("notify-battery"),
+            widget::toggler(config.notify_battery)
+                .on_toggle(|value| Message::SetAppSetting(AppSetting::NotifyBattery(value))),
+        ))
+        .add(widget::settings::item(
+            fl!("battery-low"),
+            preset_dropdown(
+                &app.battery_threshold_options,
+                &BATTERY_THRESHOLD_PRESETS,
+                config.battery_low_percent,
+                AppSetting::BatteryLow,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("battery-critical"),
+            preset_dropdown(
+                &app.battery_threshold_options,
+                &BATTERY_THRESHOLD_PRESETS,
+                config.battery_critical_percent,
+                AppSetting::BatteryCritical,
+            ),
+        ));
+
+    let behavior = widget::settings::section()
+        .title(fl!("settings-behavior"))
+        .add(widget::settings::item(
+            fl!("panel-display"),
+            preset_dropdown(
+                &app.panel_display_options,
+                &PanelDisplay::ALL,
+                config.panel_display,
+                AppSetting::PanelDisplay,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("idle-timeout"),
+            preset_dropdown(
+                &app.idle_timeout_options[1..],
+                &IDLE_TIMEOUT_PRESETS,
+                config.idle_timeout_minutes,
+                AppSetting::IdleTimeout,
+            ),
+        ))
+        .add(widget::settings::item(
+            fl!("inhibit-screen-blanking"),
+            widget::toggler(config.inhibit_screen_blanking).on_toggle(|value| {
+                Message::SetAppSetting(AppSetting::InhibitScreenBlanking(value))
+            }),
+        ))
+        .add(widget::settings::item(
+            fl!("disconnect-on-suspend"),
+            widget::toggler(config.disconnect_on_suspend)
+                .on_toggle(|value| Message::SetAppSetting(AppSetting::DisconnectOnSuspend(value))),
+        ))
+        .add(widget::settings::item(
+            fl!("reconnect-on-resume"),
+            widget::toggler(config.reconnect_on_resume)
+                .on_toggle(|value| Message::SetAppSetting(AppSetting::ReconnectOnResume(value))),
+        ))
+        .add(widget::settings::item(
+            fl!("log-level"),
+            preset_dropdown(
+                &app.log_level_options,
+                &LogLevel::ALL,
+                config.log_level,
+                AppSetting::LogLevel,
+            ),
+        ));
+
+    let diagnostics = widget::settings::section()
+        .title(fl!("settings-diagnostics"))
+        .add(widget::settings::item(
+            fl!("anonymize-diagnostics"),
+            widget::toggler(config.anonymize_diagnostics)
+                .on_toggle(|value| Message::SetAppSetting(AppSetting::AnonymizeDiagnostics(value))),
+        ))
+        .add(
+            widget::row()
+                .spacing(8)
+                .push(
+                    widget::button::text(fl!("diagnostics-copy"))
+                        .on_press(Message::ExportDiagnostics(DiagnosticsTarget::Clipboard)),
+                )
+                .push(
+                    widget::button::text(fl!("diagnostics-save"))
+                        .on_press(Message::ExportDiagnostics(DiagnosticsTarget::File)),
+                )
+                .push(
+                    widget::button::text(fl!("compatibility-check"))
+                        .on_press(Message::OpenPage(Page::Compatibility)),
+                ),
+        );
+
+    widget::column()
+        .padding(8)
+        .spacing(8)
+        .push(widget::button::text(fl!("back")).on_press(Message::Back))
+        .push(devices)
+        .push(notifications)
+        .push(behavior)
+        .push(diagnostics)
+}
+
+fn compatibility_page(app: &AppModel) -> widget::Column<'_, Message> {
+    let mut content = widget::column()
+        .padding(8)
+        .spacing(8)
+        .push(widget::button::text(fl!("back")).on_press(Message::Back))
+        .push(widget::text(fl!("compatibility")));
+
+    let Some(reports) = &app.compatibility else {
+        return content.push(widget::text(fl!("compatibility-checking")));
+    };
+    if reports.is_empty() {
+        return content.push(widget::text(fl!("compatibility-no-controllers")));
+    }
+
+    for report in reports {
+        let name = app.config.display_name(&report.addr, &report.name);
+        let mut section =
+            widget::settings::section().title(format!("{name} ({})", model_name(report.model)));
+
+        if report.findings.is_empty() {
+            section = section.add(widget::text(fl!("compatibility-ok")));
+        }
+        for finding in &report.findings {
+            let (title, explanation, fix) = finding_text(*finding);
+            let mut column = widget::column()
+                .spacing(4)
+                .push(widget::text::heading(title))
+                .push(widget::text(explanation))
+                .push(widget::text(fl!("compatibility-fix", fix = fix)));
+            if finding.helper_fix().is_some() {
+                column = column.push(fix_button(app, *finding));
+            }
+            section = section.add(column);
+        }
+
+        content = content.push(section);
+    }
+
+    if app.restart_needed {
+        content = content.push(restart_prompt(app));
+    }
+
+    content
+}
+
+fn fix_button(app: &AppModel, finding: Finding) -> Element<'_, Message> {
+    if !finding.lowers_security() {
+        return widget::button::text(fl!("compatibility-apply"))
+            .on_press(Message::ApplyFix(finding))
+            .into();
+    }
+    if app.confirm_fix != Some(finding) {
+        return widget::button::text(fl!("compatibility-apply"))
+            .on_press(Message::ConfirmFix(Some(finding)))
+            .into();
+    }
+
+    widget::column()
+        .spacing(4)
+        .push(widget::text(fl!("compatibility-confirm-security")))
+        .push(
+            widget::row()
+                .spacing(8)
+                .push(
+                    widget::button::destructive(fl!("compatibility-apply-anyway"))
+                        .on_press(Message::ApplyFix(finding)),
+                )
+                .push(widget::button::text(fl!("cancel")).on_press(Message::ConfirmFix(None))),
+        )
+        .into()
+}
+
+fn restart_prompt(app: &AppModel) -> Element<'_, Message> {
+    let mut column = widget::column()
+        .spacing(4)
+        .push(widget::text(fl!("compatibility-restart-needed")));
+
+    column = if app.confirm_restart {
+        column
+            .push(widget::text(fl!("compatibility-restart-confirm")))
+            .push(
+                widget::row()
+                    .spacing(8)
+                    .push(
+                        widget::button::destructive(fl!("compatibility-restart"))
+                            .on_press(Message::RestartBluetooth),
+                    )
+                    .push(
+                        widget::button::text(fl!("cancel"))
+                            .on_press(Message::ConfirmRestart(false)),
+                    ),
+            )
+    } else {
+        column.push(
+            widget::button::text(fl!("compatibility-restart"))
+                .on_press(Message::ConfirmRestart(true)),
+        )
+    };
+
+    widget::settings::section().add(column).into()
+}
+
+fn add_controller_page(app: &AppModel) -> widget::Column<'_, Message> {
+    let mut list = widget::list_column().padding(5).spacing(0);
+
+    if app.nearby.is_empty() {
+        list = list.add(widget::text(fl!("add-controller-searching")));
+        for models in PAIRING_GUIDES {
+            let title: Vec<String> = models.iter().map(|model| model_name(*model)).collect();
+            list = list.add(
+                widget::column()
+                    .spacing(4)
+                    .push(widget::text::heading(title.join(" / ")))
+                    .push(pairing_guide(models[0])),
+            );
+        }
+    }
+    for dev in &app.nearby {
+        let name = dev.name.as_deref().unwrap_or(&dev.addr);
+        let mut label = widget::row()
+            .align_y(Alignment::Center)
+            .spacing(4)
+            .push(widget::text(format!("{name} ({})", model_name(dev.model))));
+        let busy = app.operations.contains_key(&dev.addr);
+        if busy {
+            label = label.push(widget::text(
+                SPINNER_FRAMES[app.spinner_frame % SPINNER_FRAMES.len()],
+            ));
+        }
+
+        let details = widget::column()
+            .spacing(2)
+            .push(label)
+            .push(widget::text::caption(pairing_instructions(dev.model)));
+
+        let row = widget::row()
+            .align_y(Alignment::Center)
+            .spacing(8)
+            .push(details.width(Length::FillPortion(6)))
+            .push(
+                widget::text(fl!("signal-strength", rssi = dev.rssi)).width(Length::FillPortion(2)),
+            )
+            .push(
+                widget::container(
+                    widget::button::text(fl!("add-controller-pair"))
+                        .on_press_maybe((!busy).then(|| Message::AddController(dev.addr.clone()))),
+                )
+                .width(Length::FillPortion(2))
+                .align_x(Alignment::End),
+            );
+
+        list = list.add(row);
+    }
+
+    widget::column()
+        .padding(8)
+        .spacing(8)
+        .push(widget::button::text(fl!("back")).on_press(Message::Back))
+        .push(widget::text(fl!("add-controller")))
+        .push(widget::text(fl!("add-controller-help")))
+        .push(list)
+}
+
+fn pairing_guide(model: ControllerModel) -> widget::Row<'static, Message> {
+    widget::row()
+        .align_y(Alignment::Center)
+        .spacing(12)
+        .push(
+            widget::icon(icons::pairing_illustration(model))
+                .width(Length::Fixed(96.0))
+                .height(Length::Fixed(64.0)),
+        )
+        .push(widget::text(pairing_instructions(model)).width(Length::Fill))
+}
+
+fn pairing_instructions(model: ControllerModel) -> String {
+    match model {
+        ControllerModel::DualShock4 | ControllerModel::DualSense => {
+            fl!("pairing-steps-playstation")
+        }
+        ControllerModel::DualShock3 => fl!("pairing-steps-dualshock3"),
+        ControllerModel::Xbox => fl!("pairing-steps-xbox"),
+        ControllerModel::SwitchPro | ControllerModel::JoyCon => fl!("pairing-steps-nintendo"),
+        ControllerModel::EightBitDo => fl!("pairing-steps-8bitdo"),
+        ControllerModel::Wiimote => fl!("pairing-steps-wiimote"),
+        ControllerModel::Other => fl!("pairing-steps-other"),
+    }
+}
+
+// The model a running reconnect is waiting for, identified before BlueZ forgot the controller.
+fn reconnect_model(app: &AppModel, addr: &str) -> Option<ControllerModel> {
+    if !app.reconnecting.contains_key(addr) {
+        return None;
+    }
+    match app.operations.get(addr)?.front()? {
+        DeviceOp::Reconnect(entry) => Some(detect_model(entry.modalias.as_deref(), &entry.alias)),
+        _ => None,
+    }
+}
+
+fn model_name(model: ControllerModel) -> String {
+    match model {
+        ControllerModel::DualShock3 => fl!("model-dualshock3"),
+        ControllerModel::DualShock4 => fl!("model-dualshock4"),
+        ControllerModel::DualSense => fl!("model-dualsense"),
+        ControllerModel::Xbox => fl!("model-xbox"),
+        ControllerModel::SwitchPro => fl!("model-switch-pro"),
+        ControllerModel::JoyCon => fl!("model-joycon"),
+        ControllerModel::Wiimote => fl!("model-wiimote"),
+        ControllerModel::EightBitDo => fl!("model-8bitdo"),
+        ControllerModel::Other => fl!("model-other"),
+    }
+}
+
+fn finding_text(finding: Finding) -> (String, String, String) {
+    match finding {
+        Finding::ClassicBondedOnly => (
+            fl!("finding-classic-bonded-only"),
+            fl!("finding-classic-bonded-only-why"),
+            fl!("finding-classic-bonded-only-fix"),
+        ),
+        Finding::UhidMissing => (
+            fl!("finding-uhid-missing"),
+            fl!("finding-uhid-missing-why"),
+            fl!("finding-uhid-missing-fix"),
+        ),
+        Finding::FastConnectable => (
+            fl!("finding-fast-connectable"),
+            fl!("finding-fast-connectable-why"),
+            fl!("finding-fast-connectable-fix"),
+        ),
+        Finding::JustWorksRepairing => (
+            fl!("finding-just-works-repairing"),
+            fl!("finding-just-works-repairing-why"),
+            fl!("finding-just-works-repairing-fix"),
+        ),
+        Finding::ErtmEnabled => (
+            fl!("finding-ertm-enabled"),
+            fl!("finding-ertm-enabled-why"),
+            fl!("finding-ertm-enabled-fix"),
+        ),
+        Finding::XpadneoMissing => (
+            fl!("finding-xpadneo-missing"),
+            fl!("finding-xpadneo-missing-why"),
+            fl!("finding-xpadneo-missing-fix"),
+        ),
+        Finding::HidNintendoMissing => (
+            fl!("finding-hid-nintendo-missing"),
+            fl!("finding-hid-nintendo-missing-why"),
+            fl!("finding-hid-nintendo-missing-fix"),
+        ),
+    }
+}
+
+fn preset_dropdown<'a, T: Copy + PartialEq + 'static>(
+    options: &'a [String],
+    presets: &'static [T],
+    value: T,
+    setting: fn(T) -> AppSetting,
+) -> Element<'a, Message> {
+    let selected = presets.iter().position(|preset| *preset == value);
+    widget::dropdown(options, selected, move |index| {
+        Message::SetAppSetting(setting(presets[index]))
+    })
+    .into()
+}
+
+fn history_section(app: &AppModel) -> widget::Column<'_, Message> {
+    let header = widget::row()
+        .align_y(Alignment::Center)
+        .spacing(8)
+        .push(widget::text(fl!("history")).width(Length::Fill))
+        .push(widget::button::text(fl!("history-clear")).on_press(Message::ClearHistory));
+
+    let mut list = widget::list_column().padding(5).spacing(0);
+    for entry in &app.history {
+        let message = match entry.kind {
+            HistoryKind::Error => fl!("error-loading", error = entry.message.as_str()),
+            HistoryKind::Event => entry.message.clone(),
+        };
+        let message = match &entry.addr {
+            Some(addr) => {
+                let name = app
+                    .paired_names
+                    .get(addr)
+                    .map_or(addr.as_str(), String::as_str);
+                format!("{}: {message}", app.config.display_name(addr, name))
+            }
+            None => message,
+        };
+
+        let copy_button = widget::button::icon(icons::copy_icon())
+            .tooltip(fl!("history-copy"))
+            .on_press(Message::CopyHistory(entry.id))
+            .extra_small();
+        let dismiss_button = widget::button::icon(icons::dismiss_icon())
+            .tooltip(fl!("history-dismiss"))
+            .on_press(Message::DismissHistory(entry.id))
+            .extra_small();
+
+        let row = widget::row()
+            .align_y(Alignment::Center)
+            .spacing(8)
+            .push(widget::text(entry.time.format("%H:%M:%S").to_string()))
+            .push(widget::text(message).width(Length::Fill))
+            .push(copy_button)
+            .push(dismiss_button);
+        list = list.add(row);
+    }
+
+    widget::column().spacing(8).push(header).push(list)
+}
+
+fn device_label<'a>(app: &'a AppModel, addr: &'a str, name: &'a str) -> widget::Row<'a, Message> {
+    let label = format!("{} ({})", app.config.display_name(addr, name), addr);
+    let mut row = widget::row().align_y(Alignment::Center).spacing(4);
+
+    if let Some(color) = app.config.device(addr).and_then(|settings| settings.color) {
+        row = row.push(widget::text("●").class(cosmic::theme::Text::Color(color.color())));
+    }
+
+    row = row.push(widget::text(label));
+
+    if app.operations.contains_key(addr) {
+        row = row.push(widget::text(
+            SPINNER_FRAMES[app.spinner_frame % SPINNER_FRAMES.len()],
+        ));
+    }
+
+    row
+}
+
+fn ordered<'a, T>(app: &AppModel, items: &'a [T], addr: impl Fn(&T) -> &str) -> Vec<&'a T> {
+    let mut visible: Vec<&T> = items
+        .iter()
+        .filter(|item| app.show_hidden || !app.config.is_hidden(addr(item)))
+        .collect();
+    visible.sort_by_key(|item| !app.config.is_favorite(addr(item)));
+    visible
 }
@@ -0,0 +1,323 @@
+// Privileged helper that applies a fixed set of BlueZ configuration fixes. It is meant to be run
+// through pkexec, so it only accepts known fix names and never takes paths or values from the
+// caller, except for `--root` which the tests use to point it at a scratch directory.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::fs::{MetadataExt, chown};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+#[path = "../fixes.rs"]
+mod fixes;
+
+use fixes::HelperFix as Fix;
+
+const INPUT_CONF: &str = "etc/bluetooth/input.conf";
+const MAIN_CONF: &str = "etc/bluetooth/main.conf";
+const MODPROBE_CONF: &str = "etc/modprobe.d/cosmic-bluetooth-gamepad.conf";
+const ERTM_PARAMETER: &str = "sys/module/bluetooth/parameters/disable_ertm";
+const BACKUP_SUFFIX: &str = "cosmic-bluetooth-gamepad.bak";
+
+impl Fix {
+    fn apply(self, root: &Path) -> io::Result<()> {
+        match self {
+            Fix::ClassicBondedOnlyOff => set_ini_value(
+                &root.join(INPUT_CONF),
+                "General",
+                "ClassicBondedOnly",
+                "false",
+            ),
+            Fix::ClassicBondedOnlyOn => set_ini_value(
+                &root.join(INPUT_CONF),
+                "General",
+                "ClassicBondedOnly",
+                "true",
+            ),
+            Fix::JustWorksRepairingAlways => set_ini_value(
+                &root.join(MAIN_CONF),
+                "General",
+                "JustWorksRepairing",
+                "always",
+            ),
+            Fix::FastConnectableOn => {
+                set_ini_value(&root.join(MAIN_CONF), "General", "FastConnectable", "true")
+            }
+            Fix::DisableErtm => {
+                write_file(
+                    &root.join(MODPROBE_CONF),
+                    "options bluetooth disable_ertm=Y\n",
+                )?;
+                // Also apply it to the running kernel so a reboot isn't needed.
+                let parameter = root.join(ERTM_PARAMETER);
+                if parameter.exists() {
+                    fs::write(parameter, "Y")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("cosmic-bluetooth-gamepad-helper: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut root = PathBuf::from("/");
+    let mut restart = false;
+    let mut fixes = Vec::new();
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => {
+                // Never let an unprivileged caller redirect root-owned writes through pkexec.
+                if env::var_os("PKEXEC_UID").is_some() {
+                    return Err("--root is not allowed through pkexec".to_string());
+                }
+                root = args
+                    .next()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| "--root needs a directory".to_string())?;
+            }
+            "--restart-bluetooth" => restart = true,
+            name => {
+                let fix = Fix::parse(name).ok_or_else(|| format!("unknown fix: {name}"))?;
+                fixes.push(fix);
+            }
+        }
+    }
+
+    if fixes.is_empty() && !restart {
+        let names: Vec<_> = Fix::ALL.iter().map(|fix| fix.name()).collect();
+        return Err(format!(
+            "usage: [--root DIR] [--restart-bluetooth] [{}]...",
+            names.join("|")
+        ));
+    }
+
+    if restart && root != Path::new("/") {
+        return Err("--restart-bluetooth cannot be combined with --root".to_string());
+    }
+
+    for fix in fixes {
+        fix.apply(&root)
+            .map_err(|err| format!("failed to apply {}: {err}", fix.name()))?;
+    }
+
+    if restart {
+        let status = Command::new("systemctl")
+            .args(["restart", "bluetooth.service"])
+            .status()
+            .map_err(|err| format!("failed to run systemctl: {err}"))?;
+        if !status.success() {
+            return Err(format!("restarting bluetooth.service failed: {status}"));
+        }
+    }
+
+    Ok(())
+}
+
+fn set_ini_value(path: &Path, section: &str, key: &str, value: &str) -> io::Result<()> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    write_file(path, &with_ini_value(&contents, section, key, value))
+}
+
+// Sets `key = value` inside `[section]`, replacing an existing or commented-out entry so the
+// surrounding documentation in the stock BlueZ files stays where it is.
+fn with_ini_value(contents: &str, section: &str, key: &str, value: &str) -> String {
+    let entry = format!("{key} = {value}");
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+
+    let header = format!("[{section}]");
+    let Some(start) = lines.iter().position(|line| line.trim() == header) else {
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(header);
+        lines.push(entry);
+        return lines.join("\n") + "\n";
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let existing = (start + 1..end).find(|&i| entry_key(&lines[i], false) == Some(key));
+    let commented = (start + 1..end).find(|&i| entry_key(&lines[i], true) == Some(key));
+    match existing.or(commented) {
+        Some(i) => lines[i] = entry,
+        None => lines.insert(start + 1, entry),
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn entry_key(line: &str, commented: bool) -> Option<&str> {
+    let line = line.trim();
+    let line = if commented {
+        line.strip_prefix('#')?.trim_start()
+    } else {
+        line
+    };
+    let (key, _) = line.split_once('=')?;
+    let key = key.trim();
+    (!key.is_empty() && !key.contains(char::is_whitespace)).then_some(key)
+}
+
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Keep the very first version around so the original configuration can always be restored.
+    if path.exists() {
+        let backup = backup_path(path);
+        if !backup.exists() {
+            fs::copy(path, backup)?;
+        }
+    }
+
+    // The rename gives the file the temp file's mode and owner, so carry the original's over
+    // and let restorecon put back its SELinux label where there is one.
+    let tmp = path.with_extension("cosmic-bluetooth-gamepad.tmp");
+    fs::write(&tmp, contents)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(&tmp, metadata.permissions())?;
+        chown(&tmp, Some(metadata.uid()), Some(metadata.gid()))?;
+    }
+    fs::rename(tmp, path)?;
+    let _ = Command::new("restorecon").arg(path).output();
+    Ok(())
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(BACKUP_SUFFIX);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_root(name: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!(
+            "cosmic-bluetooth-gamepad-helper-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        list.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn replaces_commented_entry_and_keeps_backup() {
+        let root = scratch_root("commented");
+        let input = root.join(INPUT_CONF);
+        fs::create_dir_all(input.parent().unwrap()).unwrap();
+        let original = "[General]\n# Some docs\n#ClassicBondedOnly=true\nIdleTimeout=0\n";
+        fs::write(&input, original).unwrap();
+
+        run(args(&[
+            "--root",
+            root.to_str().unwrap(),
+            "classic-bonded-only-off",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&input).unwrap(),
+            "[General]\n# Some docs\nClassicBondedOnly = false\nIdleTimeout=0\n"
+        );
+        assert_eq!(fs::read_to_string(backup_path(&input)).unwrap(), original);
+
+        run(args(&[
+            "--root",
+            root.to_str().unwrap(),
+            "classic-bonded-only-on",
+        ]))
+        .unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&input)).unwrap(), original);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn adds_missing_section_and_modprobe_option() {
+        let root = scratch_root("missing");
+        let main = root.join(MAIN_CONF);
+        fs::create_dir_all(main.parent().unwrap()).unwrap();
+        fs::write(&main, "[Policy]\nAutoEnable=true\n").unwrap();
+
+        run(args(&[
+            "--root",
+            root.to_str().unwrap(),
+            "just-works-repairing-always",
+            "fast-connectable-on",
+            "disable-ertm",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&main).unwrap(),
+            "[Policy]\nAutoEnable=true\n\n[General]\nFastConnectable = true\nJustWorksRepairing = always\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join(MODPROBE_CONF)).unwrap(),
+            "options bluetooth disable_ertm=Y\n"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn keeps_file_mode_when_replacing() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = scratch_root("mode");
+        let input = root.join(INPUT_CONF);
+        fs::create_dir_all(input.parent().unwrap()).unwrap();
+        fs::write(&input, "[General]\n").unwrap();
+        fs::set_permissions(&input, fs::Permissions::from_mode(0o640)).unwrap();
+
+        run(args(&[
+            "--root",
+            root.to_str().unwrap(),
+            "classic-bonded-only-off",
+        ]))
+        .unwrap();
+
+        let mode = fs::metadata(&input).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_fixes_and_restart_with_root() {
+        assert!(run(args(&["rm-rf"])).is_err());
+        assert!(run(args(&["--root", "/tmp", "--restart-bluetooth"])).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use super::Config;
+use crate::app::APP_ID;
+use cosmic::cosmic_config::{self, CosmicConfigEntry};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+type Entries = BTreeMap<String, String>;
+
+pub fn load(context: &cosmic_config::Config) -> Config {
+    let entries = version_dir(Config::VERSION)
+        .map(|dir| read_entries(&dir))
+        .unwrap_or_else(|| Ok(Entries::new()));
+
+    match entries {
+        Ok(entries) if entries.is_empty() => {
+            if let Some(config) = migrate_previous(context) {
+                return config;
+            }
+        }
+        Ok(entries) => {
+            let (_config, offending) = migrate(Config::VERSION, entries);
+            warn_offending(Config::VERSION, &offending);
+        }
+        Err(err) => tracing::warn!("failed to read config: {err}"),
+    }
+
+    match Config::get_entry(context) {
+        Ok(config) => config,
+        Err((_errors, config)) => config,
+    }
+}
+
+fn migrate_previous(context: &cosmic_config::Config) -> Option<Config> {
+    for version in (1..Config::VERSION).rev() {
+        let Some(dir) = version_dir(version) else {
+            continue;
+        };
+        let Ok(entries) = read_entries(&dir) else {
+            continue;
+        };
+        if entries.is_empty() {
+            continue;
+        }
+
+        let (config, offending) = migrate(version, entries);
+        warn_offending(version, &offending);
+
+        if let Err(err) = config.write_entry(context) {
+            tracing::warn!("failed to write migrated config: {err}");
+        } else {
+            tracing::info!("migrated config from v{version} to v{}", Config::VERSION);
+        }
+
+        return Some(config);
+    }
+
+    None
+}
+
+fn warn_offending(version: u64, offending: &[String]) {
+    if !offending.is_empty() {
+        tracing::warn!("ignored config v{version} keys: {}", offending.join("; "));
+    }
+}
+
+fn version_dir(version: u64) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("cosmic").join(APP_ID).join(format!("v{version}")))
+}
+
+fn read_entries(dir: &Path) -> io::Result<Entries> {
+    let mut entries = Entries::new();
+
+    let dir = match fs::read_dir(dir) {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(err) => return Err(err),
+    };
+
+    for entry in dir {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let key = entry.file_name().to_string_lossy().into_owned();
+        if key.starts_with('.') {
+            continue;
+        }
+
+        entries.insert(key, fs::read_to_string(entry.path())?);
+    }
+
+    Ok(entries)
+}
+
+fn migrate(version: u64, entries: Entries) -> (Config, Vec<String>) {
+    match version {
+        1 => from_v1(entries),
+        2 => from_v2(entries),
+        _ => (
+            Config::default(),
+            vec![format!("v{version} (unsupported config version)")],
+        ),
+    }
+}
+
+fn from_v1(mut entries: Entries) -> (Config, Vec<String>) {
+    let mut offending = Vec::new();
+
+    entries.remove("demo");
+    let slots = take::<HashMap<String, u8>>(&mut entries, "player_slots", &mut offending);
+    let touchpads = take::<HashSet<String>>(&mut entries, "inhibited_touchpads", &mut offending);
+    let motion_sensors =
+        take::<HashSet<String>>(&mut entries, "inhibited_motion_sensors", &mut offending);
+    let timeouts = take::<HashMap<String, u32>>(&mut entries, "idle_timeouts", &mut offending);
+
+    // The remaining v1 keys kept their name and type in v2.
+    let mut config = take_fields(entries, &mut offending);
+
+    if let Some(slots) = slots {
+        config.set_player_slots(&slots);
+    }
+    for addr in touchpads.into_iter().flatten() {
+        config.device_mut(&addr).inhibit_touchpad = true;
+    }
+    for addr in motion_sensors.into_iter().flatten() {
+        config.device_mut(&addr).inhibit_motion_sensors = true;
+    }
+    for (addr, minutes) in timeouts.into_iter().flatten() {
+        config.device_mut(&addr).idle_timeout_minutes = Some(minutes);
+    }
+
+    (config, offending)
+}
+
+fn from_v2(entries: Entries) -> (Config, Vec<String>) {
+    let mut offending = Vec::new();
+    let config = take_fields(entries, &mut offending);
+    (config, offending)
+}
+
+// Each key is checked on its own first so one bad value doesn't discard the others, then the
+// valid ones are read together as a Config.
+fn take_fields(entries: Entries, offending: &mut Vec<String>) -> Config {
+    let mut fields = Vec::new();
+    for (key, raw) in entries {
+        match ron::from_str::<Config>(&format!("({key}: {raw})")) {
+            Ok(_) => fields.push(format!("{key}: {raw}")),
+            Err(err) => offending.push(format!("{key} ({err})")),
+        }
+    }
+
+    ron::from_str(&format!("({})", fields.join(", "))).unwrap_or_else(|err| {
+        offending.push(format!("config ({err})"));
+        Config::default()
+    })
+}
+
+fn take<T: DeserializeOwned>(
+    entries: &mut Entries,
+    key: &str,
+    offending: &mut Vec<String>,
+) -> Option<T> {
+    let raw = entries.remove(key)?;
+    match ron::from_str(&raw) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            offending.push(format!("{key} ({err})"));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DeviceSettings;
+
+    fn fixture(name: &str) -> Entries {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/config")
+            .join(name);
+        read_entries(&dir).unwrap()
+    }
+
+    #[test]
+    fn migrates_initial_v1() {
+        let (config, offending) = migrate(1, fixture("v1-initial"));
+
+        assert_eq!(config, Config::default());
+        assert!(offending.is_empty());
+    }
+
+    #[test]
+    fn migrates_v1_device_maps() {
+        let (config, offending) = migrate(1, fixture("v1"));
+
+        assert!(offending.is_empty(), "{offending:?}");
+        assert_eq!(config.idle_timeout_minutes, 15);
+        assert!(config.inhibit_screen_blanking);
+        assert!(config.reconnect_on_resume);
+
+        let dualsense = config.device("AA:BB:CC:DD:EE:01").unwrap();
+        assert_eq!(
+            dualsense,
+            &DeviceSettings {
+                player_slot: Some(1),
+                inhibit_touchpad: true,
+                inhibit_motion_sensors: true,
+                idle_timeout_minutes: Some(30),
+                ..Default::default()
+            }
+        );
+
+        let switch_pro = config.device("AA:BB:CC:DD:EE:02").unwrap();
+        assert_eq!(switch_pro.player_slot, Some(2));
+        assert_eq!(switch_pro.idle_timeout_minutes, Some(0));
+    }
+
+    #[test]
+    fn reports_offending_v1_keys() {
+        let (config, offending) = migrate(1, fixture("v1-invalid"));
+
+        assert_eq!(config.player_slot("AA:BB:CC:DD:EE:01"), Some(3));
+        assert_eq!(config.idle_timeout_minutes, 0);
+        assert_eq!(offending.len(), 2, "{offending:?}");
+        assert!(offending[0].starts_with("idle_timeout_minutes"));
+        assert!(offending[1].starts_with("unexpected_key"));
+    }
+
+    #[test]
+    fn loads_v2() {
+        let (config, offending) = migrate(2, fixture("v2"));
+
+        assert!(offending.is_empty(), "{offending:?}");
+        assert!(config.disconnect_on_suspend);
+
+        let dualsense = config.device("AA:BB:CC:DD:EE:01").unwrap();
+        assert_eq!(dualsense.nickname.as_deref(), Some("Couch pad"));
+        assert_eq!(dualsense.color, Some(crate::config::ColorTag::Blue));
+        assert!(dualsense.favorite);
+        assert!(dualsense.auto_connect);
+    }
+
+    #[test]
+    fn keeps_valid_v2_keys_next_to_offending_ones() {
+        let entries = Entries::from([
+            ("disconnect_on_suspend".into(), "true".into()),
+            ("idle_timeout_minutes".into(), "\"ten\"".into()),
+            ("removed_key".into(), "1".into()),
+        ]);
+        let (config, offending) = migrate(2, entries);
+
+        assert!(config.disconnect_on_suspend);
+        assert_eq!(config.idle_timeout_minutes, 0);
+        assert_eq!(offending.len(), 2, "{offending:?}");
+        assert!(offending[0].starts_with("idle_timeout_minutes"));
+        assert!(offending[1].starts_with("removed_key"));
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let (config, offending) = migrate(3, fixture("v2"));
+
+        assert_eq!(config, Config::default());
+        assert_eq!(offending, ["v3 (unsupported config version)"]);
+    }
+}
@@ -0,0 +1,311 @@
+pub mod migrate;
+
+use crate::services::{DiscoveryFilter, InputNodeKind, ListFilter};
+use cosmic::cosmic_config::{self, CosmicConfigEntry, cosmic_config_derive::CosmicConfigEntry};
+use cosmic::iced::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+pub const IDLE_TIMEOUT_PRESETS: [u32; 6] = [0, 5, 10, 15, 30, 60];
+pub const REFRESH_INTERVAL_PRESETS: [u32; 4] = [5, 10, 30, 60];
+pub const TIMEOUT_PRESETS: [u32; 5] = [15, 30, 60, 90, 120];
+pub const BATTERY_THRESHOLD_PRESETS: [u8; 6] = [0, 5, 10, 15, 20, 30];
+pub const AUTO_CONNECT_ATTEMPT_PRESETS: [u32; 4] = [1, 3, 5, 10];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceFilter {
+    #[default]
+    All,
+    Gamepads,
+}
+
+impl DeviceFilter {
+    pub const ALL: [DeviceFilter; 2] = [DeviceFilter::All, DeviceFilter::Gamepads];
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanelDisplay {
+    #[default]
+    Icon,
+    ConnectedCount,
+    LowestBattery,
+}
+
+impl PanelDisplay {
+    pub const ALL: [PanelDisplay; 3] = [
+        PanelDisplay::Icon,
+        PanelDisplay::ConnectedCount,
+        PanelDisplay::LowestBattery,
+    ];
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiscoveryTransport {
+    #[default]
+    Auto,
+    BrEdr,
+    Le,
+}
+
+impl DiscoveryTransport {
+    pub const ALL: [DiscoveryTransport; 3] = [
+        DiscoveryTransport::Auto,
+        DiscoveryTransport::BrEdr,
+        DiscoveryTransport::Le,
+    ];
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogLevel {
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorTag {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl ColorTag {
+    pub const ALL: [ColorTag; 6] = [
+        ColorTag::Red,
+        ColorTag::Orange,
+        ColorTag::Yellow,
+        ColorTag::Green,
+        ColorTag::Blue,
+        ColorTag::Purple,
+    ];
+
+    pub fn color(self) -> Color {
+        match self {
+            ColorTag::Red => Color::from_rgb8(0xe0, 0x1b, 0x24),
+            ColorTag::Orange => Color::from_rgb8(0xff, 0x78, 0x00),
+            ColorTag::Yellow => Color::from_rgb8(0xf6, 0xd3, 0x2d),
+            ColorTag::Green => Color::from_rgb8(0x33, 0xd1, 0x7a),
+            ColorTag::Blue => Color::from_rgb8(0x35, 0x84, 0xe4),
+            ColorTag::Purple => Color::from_rgb8(0x91, 0x41, 0xac),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    pub nickname: Option<String>,
+    pub alias: Option<String>,
+    pub color: Option<ColorTag>,
+    pub hidden: bool,
+    pub favorite: bool,
+    pub player_slot: Option<u8>,
+    pub auto_connect: bool,
+    pub idle_timeout_minutes: Option<u32>,
+    pub inhibit_touchpad: bool,
+    pub inhibit_motion_sensors: bool,
+    pub sixaxis_dismissed: bool,
+    pub game_controller: bool,
+}
+
+#[derive(Debug, Clone, CosmicConfigEntry, Eq, PartialEq, Serialize, Deserialize)]
+#[version = 2]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub devices: BTreeMap<String, DeviceSettings>,
+    pub idle_timeout_minutes: u32,
+    pub idle_stick_threshold: u8,
+    pub inhibit_screen_blanking: bool,
+    pub screen_inhibit_minutes: u32,
+    pub disconnect_on_suspend: bool,
+    pub reconnect_on_resume: bool,
+    pub refresh_interval_secs: u32,
+    pub refresh_when_closed: bool,
+    pub reconnect_timeout_secs: u32,
+    pub discovery_timeout_secs: u32,
+    pub discovery_transport: DiscoveryTransport,
+    pub device_filter: DeviceFilter,
+    pub notify_connection: bool,
+    pub notify_battery: bool,
+    pub battery_low_percent: u8,
+    pub battery_critical_percent: u8,
+    pub panel_display: PanelDisplay,
+    pub adapter: Option<String>,
+    pub auto_connect_max_attempts: u32,
+    pub auto_repair: bool,
+    pub log_level: LogLevel,
+    pub anonymize_diagnostics: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            devices: BTreeMap::new(),
+            idle_timeout_minutes: 0,
+            idle_stick_threshold: 10,
+            inhibit_screen_blanking: false,
+            screen_inhibit_minutes: 5,
+            disconnect_on_suspend: false,
+            reconnect_on_resume: false,
+            refresh_interval_secs: 10,
+            refresh_when_closed: true,
+            reconnect_timeout_secs: 60,
+            discovery_timeout_secs: 60,
+            discovery_transport: DiscoveryTransport::Auto,
+            device_filter: DeviceFilter::All,
+            notify_connection: false,
+            notify_battery: false,
+            battery_low_percent: 20,
+            battery_critical_percent: 10,
+            panel_display: PanelDisplay::Icon,
+            adapter: None,
+            auto_connect_max_attempts: 5,
+            auto_repair: false,
+            log_level: LogLevel::Warn,
+            anonymize_diagnostics: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn device(&self, addr: &str) -> Option<&DeviceSettings> {
+        self.devices.get(addr)
+    }
+
+    pub fn device_mut(&mut self, addr: &str) -> &mut DeviceSettings {
+        self.devices.entry(addr.to_string()).or_default()
+    }
+
+    // Only controllers missing from BlueZ's whole bonded set, not the filtered list, keep the
+    // presence scan running.
+    pub fn awaits_repair(&self, bonded: &[String]) -> bool {
+        self.auto_repair
+            && self
+                .devices
+                .iter()
+                .any(|(addr, settings)| settings.game_controller && !bonded.contains(addr))
+    }
+
+    pub fn display_name<'a>(&'a self, addr: &str, name: &'a str) -> &'a str {
+        self.device(addr)
+            .and_then(|settings| settings.nickname.as_deref())
+            .unwrap_or(name)
+    }
+
+    pub fn is_hidden(&self, addr: &str) -> bool {
+        self.device(addr).is_some_and(|settings| settings.hidden)
+    }
+
+    pub fn is_favorite(&self, addr: &str) -> bool {
+        self.device(addr).is_some_and(|settings| settings.favorite)
+    }
+
+    pub fn player_slot(&self, addr: &str) -> Option<u8> {
+        self.device(addr).and_then(|settings| settings.player_slot)
+    }
+
+    pub fn player_slots(&self) -> HashMap<String, u8> {
+        self.devices
+            .iter()
+            .filter_map(|(addr, settings)| settings.player_slot.map(|slot| (addr.clone(), slot)))
+            .collect()
+    }
+
+    pub fn set_player_slots(&mut self, slots: &HashMap<String, u8>) {
+        for (addr, settings) in &mut self.devices {
+            settings.player_slot = slots.get(addr).copied();
+        }
+        for (addr, slot) in slots {
+            self.device_mut(addr).player_slot = Some(*slot);
+        }
+    }
+
+    pub fn idle_timeout(&self, addr: &str) -> Option<Duration> {
+        let minutes = self
+            .device(addr)
+            .and_then(|settings| settings.idle_timeout_minutes)
+            .unwrap_or(self.idle_timeout_minutes);
+        (minutes > 0).then(|| Duration::from_secs(u64::from(minutes) * 60))
+    }
+
+    pub fn watches_activity(&self, addr: &str) -> bool {
+        self.inhibit_screen_blanking || self.idle_timeout(addr).is_some()
+    }
+
+    pub fn auto_connects(&self, addr: &str) -> bool {
+        self.device(addr)
+            .is_some_and(|settings| settings.auto_connect)
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.refresh_interval_secs.max(1)))
+    }
+
+    pub fn reconnect_timeout(&self) -> Duration {
+        Duration::from_secs(u64::from(self.reconnect_timeout_secs))
+    }
+
+    pub fn discovery_timeout(&self) -> Duration {
+        Duration::from_secs(u64::from(self.discovery_timeout_secs))
+    }
+
+    pub fn discovery_filter(&self) -> DiscoveryFilter {
+        let transport = match self.discovery_transport {
+            DiscoveryTransport::Auto => "auto",
+            DiscoveryTransport::BrEdr => "bredr",
+            DiscoveryTransport::Le => "le",
+        };
+        DiscoveryFilter {
+            transport,
+            ..DiscoveryFilter::default()
+        }
+    }
+
+    pub fn list_filter(&self) -> ListFilter {
+        ListFilter {
+            adapter: self.adapter.clone(),
+            gamepads_only: self.device_filter == DeviceFilter::Gamepads,
+        }
+    }
+
+    pub fn battery_level(&self, percent: u8) -> Option<u8> {
+        [self.battery_critical_percent, self.battery_low_percent]
+            .into_iter()
+            .find(|threshold| *threshold > 0 && percent <= *threshold)
+    }
+
+    pub fn input_inhibited(&self, addr: &str, kind: InputNodeKind) -> bool {
+        self.device(addr).is_some_and(|settings| match kind {
+            InputNodeKind::Touchpad => settings.inhibit_touchpad,
+            InputNodeKind::MotionSensors => settings.inhibit_motion_sensors,
+            InputNodeKind::Gamepad => false,
+        })
+    }
+
+    pub fn set_input_inhibited(&mut self, addr: &str, kind: InputNodeKind, inhibited: bool) {
+        match kind {
+            InputNodeKind::Touchpad => self.device_mut(addr).inhibit_touchpad = inhibited,
+            InputNodeKind::MotionSensors => {
+                self.device_mut(addr).inhibit_motion_sensors = inhibited;
+            }
+            InputNodeKind::Gamepad => {}
+        }
+    }
+}
@@ -0,0 +1,39 @@
+// Fixes the privileged helper knows how to apply. Both the applet and the helper include this
+// file, so the names passed on the pkexec command line can't drift apart.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelperFix {
+    ClassicBondedOnlyOff,
+    ClassicBondedOnlyOn,
+    JustWorksRepairingAlways,
+    FastConnectableOn,
+    DisableErtm,
+}
+
+impl HelperFix {
+    pub fn name(self) -> &'static str {
+        match self {
+            HelperFix::ClassicBondedOnlyOff => "classic-bonded-only-off",
+            HelperFix::ClassicBondedOnlyOn => "classic-bonded-only-on",
+            HelperFix::JustWorksRepairingAlways => "just-works-repairing-always",
+            HelperFix::FastConnectableOn => "fast-connectable-on",
+            HelperFix::DisableErtm => "disable-ertm",
+        }
+    }
+}
+
+// Only the helper parses names.
+#[allow(dead_code)]
+impl HelperFix {
+    pub const ALL: [HelperFix; 5] = [
+        HelperFix::ClassicBondedOnlyOff,
+        HelperFix::ClassicBondedOnlyOn,
+        HelperFix::JustWorksRepairingAlways,
+        HelperFix::FastConnectableOn,
+        HelperFix::DisableErtm,
+    ];
+
+    pub fn parse(name: &str) -> Option<HelperFix> {
+        HelperFix::ALL.into_iter().find(|fix| fix.name() == name)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::app::APP_ID;
+use crate::config::DeviceSettings;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ReconnectStep {
+    Removing,
+    Discovering,
+    Pairing,
+    Trusting,
+    Connecting,
+    Restoring,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub addr: String,
+    pub alias: String,
+    pub trusted: bool,
+    #[serde(default)]
+    pub blocked: bool,
+    #[serde(default)]
+    pub wake_allowed: Option<bool>,
+    #[serde(default)]
+    pub settings: Option<DeviceSettings>,
+    #[serde(default)]
+    pub modalias: Option<String>,
+    pub step: ReconnectStep,
+}
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+pub fn load() -> io::Result<Vec<JournalEntry>> {
+    let _guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    read(&journal_path()?)
+}
+
+pub fn record(entry: &JournalEntry) -> io::Result<()> {
+    let _guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    record_at(&journal_path()?, entry)
+}
+
+pub fn finish(addr: &str) -> io::Result<()> {
+    let _guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    finish_at(&journal_path()?, addr)
+}
+
+fn record_at(path: &Path, entry: &JournalEntry) -> io::Result<()> {
+    let mut entries = read(path)?;
+    entries.retain(|known| known.addr != entry.addr);
+    entries.push(entry.clone());
+    write(path, &entries)
+}
+
+fn finish_at(path: &Path, addr: &str) -> io::Result<()> {
+    let mut entries = read(path)?;
+    entries.retain(|known| known.addr != addr);
+    write(path, &entries)
+}
+
+fn journal_path() -> io::Result<PathBuf> {
+    dirs::state_dir()
+        .map(|dir| dir.join(APP_ID).join("reconnect-journal.ron"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))
+}
+
+fn read(path: &Path) -> io::Result<Vec<JournalEntry>> {
+    match fs::read_to_string(path) {
+        Ok(raw) => {
+            ron::from_str(&raw).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn write(path: &Path, entries: &[JournalEntry]) -> io::Result<()> {
+    if entries.is_empty() {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let raw = ron::ser::to_string_pretty(entries, ron::ser::PrettyConfig::default())
+        .map_err(io::Error::other)?;
+
+    // Write to a temporary file first so a crash never leaves a truncated journal behind.
+    let tmp = path.with_extension("ron.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(raw.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn scratch_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "cosmic-bluetooth-gamepad-journal-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(APP_ID).join("reconnect-journal.ron")
+    }
+
+    fn entry(addr: &str, step: ReconnectStep) -> JournalEntry {
+        JournalEntry {
+            addr: addr.to_string(),
+            alias: "Wireless Controller".to_string(),
+            trusted: true,
+            blocked: false,
+            wake_allowed: Some(true),
+            settings: Some(DeviceSettings {
+                nickname: Some("Couch pad".to_string()),
+                player_slot: Some(2),
+                ..Default::default()
+            }),
+            modalias: Some("usb:v054Cp0CE6d0100".to_string()),
+            step,
+        }
+    }
+
+    #[test]
+    fn record_and_finish_round_trip() {
+        let path = scratch_path("round-trip");
+        let first = entry("AA:BB:CC:DD:EE:01", ReconnectStep::Removing);
+        let second = entry("AA:BB:CC:DD:EE:02", ReconnectStep::Pairing);
+
+        record_at(&path, &first).unwrap();
+        record_at(&path, &second).unwrap();
+        assert_eq!(read(&path).unwrap(), [first, second.clone()]);
+
+        finish_at(&path, "AA:BB:CC:DD:EE:01").unwrap();
+        assert_eq!(read(&path).unwrap(), [second]);
+
+        finish_at(&path, "AA:BB:CC:DD:EE:02").unwrap();
+        assert!(!path.exists());
+        assert!(read(&path).unwrap().is_empty());
+
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn records_each_step_in_order() {
+        let path = scratch_path("steps");
+        let steps = [
+            ReconnectStep::Removing,
+            ReconnectStep::Discovering,
+            ReconnectStep::Pairing,
+            ReconnectStep::Trusting,
+            ReconnectStep::Connecting,
+            ReconnectStep::Restoring,
+        ];
+        assert!(steps.is_sorted());
+
+        for step in steps {
+            record_at(&path, &entry("AA:BB:CC:DD:EE:01", step)).unwrap();
+            let entries = read(&path).unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].step, step);
+        }
+
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn replaces_journal_atomically() {
+        let path = scratch_path("atomic");
+        let tmp = path.with_extension("ron.tmp");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        // A temporary file left behind by a crash mid-write is overwritten, and the journal
+        // itself is only ever replaced by a complete file.
+        fs::write(&tmp, "[(addr: \"AA:BB").unwrap();
+        record_at(&path, &entry("AA:BB:CC:DD:EE:01", ReconnectStep::Trusting)).unwrap();
+        assert!(!tmp.exists());
+        assert_eq!(read(&path).unwrap().len(), 1);
+
+        fs::write(&path, "[(addr: \"AA:BB").unwrap();
+        assert_eq!(read(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reads_entries_without_optional_fields() {
+        let path = scratch_path("old-format");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            "[(addr: \"AA:BB:CC:DD:EE:01\", alias: \"Pad\", trusted: false, step: Connecting)]",
+        )
+        .unwrap();
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries[0].step, ReconnectStep::Connecting);
+        assert_eq!(entries[0].settings, None);
+        assert_eq!(entries[0].modalias, None);
+
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+}
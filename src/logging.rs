@@ -0,0 +1,42 @@
+use crate::config::LogLevel;
+use std::sync::OnceLock;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Registry, reload};
+
+static FILTER: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+pub fn init(level: LogLevel) {
+    let (filter, handle) = reload::Layer::new(level_filter(level));
+    let registry = tracing_subscriber::registry().with(filter);
+
+    // Prefer the journal so field reports can be pulled with `journalctl`; fall back to stderr
+    // when the applet runs outside a systemd session.
+    let result = match tracing_journald::layer() {
+        Ok(journald) => registry.with(journald).try_init(),
+        Err(_) => registry
+            .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+            .try_init(),
+    };
+
+    if result.is_ok() {
+        let _ = FILTER.set(handle);
+    }
+}
+
+pub fn set_level(level: LogLevel) {
+    if let Some(handle) = FILTER.get() {
+        let _ = handle.modify(|filter| *filter = level_filter(level));
+    }
+}
+
+fn level_filter(level: LogLevel) -> LevelFilter {
+    match level {
+        LogLevel::Error => LevelFilter::ERROR,
+        LogLevel::Warn => LevelFilter::WARN,
+        LogLevel::Info => LevelFilter::INFO,
+        LogLevel::Debug => LevelFilter::DEBUG,
+        LogLevel::Trace => LevelFilter::TRACE,
+    }
+}
@@ -1,9 +1,13 @@
 mod app;
 mod config;
+mod fixes;
 mod i18n;
+mod journal;
+mod logging;
 mod services;
 
 fn main() -> cosmic::iced::Result {
+    logging::init(config::LogLevel::default());
     let requested_languages = i18n_embed::DesktopLanguageRequester::requested_languages();
     i18n::init(&requested_languages);
     cosmic::applet::run::<app::AppModel>(())
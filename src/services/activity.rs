@@ -0,0 +1,87 @@
+use super::input::{InputNodeKind, list_input_nodes};
+use evdev::{Device, EventStream, EventSummary};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+struct Axis {
+    range: i32,
+    last: i32,
+}
+
+pub struct ActivityMonitor {
+    stream: EventStream,
+    axes: HashMap<u16, Axis>,
+    threshold: u8,
+}
+
+impl ActivityMonitor {
+    pub fn open(addr: &str, threshold: u8) -> io::Result<Option<Self>> {
+        let Some(path) = find_gamepad_event_device(addr)? else {
+            return Ok(None);
+        };
+
+        let device = Device::open(path)?;
+        let axes = device
+            .get_absinfo()?
+            .map(|(code, info)| {
+                let axis = Axis {
+                    range: info.maximum().saturating_sub(info.minimum()),
+                    last: info.value(),
+                };
+                (code.0, axis)
+            })
+            .collect();
+
+        Ok(Some(Self {
+            stream: device.into_event_stream()?,
+            axes,
+            threshold,
+        }))
+    }
+
+    pub async fn next_activity(&mut self) -> io::Result<()> {
+        loop {
+            let event = self.stream.next_event().await?;
+
+            let active = match event.destructure() {
+                EventSummary::Key(_, _, value) => value == 1,
+                EventSummary::AbsoluteAxis(_, code, value) => match self.axes.get_mut(&code.0) {
+                    Some(axis) => {
+                        let noise = axis.range as i64 * self.threshold as i64 / 100;
+                        let moved = (value as i64 - axis.last as i64).abs() > noise;
+                        if moved {
+                            axis.last = value;
+                        }
+                        moved
+                    }
+                    None => true,
+                },
+                _ => false,
+            };
+
+            if active {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn find_gamepad_event_device(addr: &str) -> io::Result<Option<PathBuf>> {
+    for node in list_input_nodes(addr)? {
+        if node.kind != InputNodeKind::Gamepad {
+            continue;
+        }
+
+        for entry in fs::read_dir(&node.path)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("event") {
+                return Ok(Some(PathBuf::from("/dev/input").join(name.as_ref())));
+            }
+        }
+    }
+
+    Ok(None)
+}
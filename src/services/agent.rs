@@ -0,0 +1,248 @@
+use super::Bluez;
+use super::bluez::Properties;
+use super::compat::{ControllerModel, detect_model};
+use std::fs;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use zbus::{DBusError, Proxy, interface};
+
+const AGENT_PATH: &str = "/com/keewee/CosmicBluetoothGamepad/agent";
+
+// Names and IDs BlueZ's built-in wiimote plugin answers the PIN request for.
+const WIIMOTE_PLUGIN_NAMES: [&str; 4] = [
+    "Nintendo RVL-CNT-01",
+    "Nintendo RVL-CNT-01-TR",
+    "Nintendo RVL-CNT-01-UC",
+    "Nintendo RVL-WBC-01",
+];
+const WIIMOTE_PLUGIN_MODALIASES: [&str; 2] = ["v057ep0306", "v057ep0330"];
+
+#[derive(Debug, DBusError)]
+#[zbus(prefix = "org.bluez.Error")]
+enum AgentError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Rejected(String),
+}
+
+// Only registered while the applet pairs a Wii controller, so every other pairing keeps going
+// through the desktop's own agent.
+struct WiiPinAgent {
+    device: OwnedObjectPath,
+    pin: String,
+}
+
+#[interface(name = "org.bluez.Agent1")]
+impl WiiPinAgent {
+    fn release(&self) {}
+
+    fn request_pin_code(&self, device: OwnedObjectPath) -> Result<String, AgentError> {
+        if device != self.device {
+            return Err(AgentError::Rejected("Unexpected device".into()));
+        }
+        Ok(self.pin.clone())
+    }
+
+    fn cancel(&self) {}
+}
+
+pub fn is_wii_controller(dev: &Properties) -> bool {
+    let name = get_string(dev, "Name").unwrap_or_default();
+    detect_model(get_string(dev, "Modalias").as_deref(), &name) == ControllerModel::Wiimote
+}
+
+// Wii Remotes and Wii U Pro Controllers use legacy pairing with a PIN made of an address's six
+// raw bytes in reverse order. BlueZ's wiimote plugin passes those bytes on as they are, so it is
+// left to answer whenever it knows the device.
+pub async fn pair_with_wii_pin(
+    bluez: &Bluez,
+    dev: &Proxy<'_>,
+    props: &Properties,
+) -> zbus::Result<()> {
+    if wiimote_plugin_handles(props) {
+        dev.call_method("Pair", &()).await?;
+        return Ok(());
+    }
+
+    // Pairing with the sync button expects the host's address, which is also the only way the
+    // controller reconnects on its own later. The remote's own address (1+2 pairing) is the
+    // fallback.
+    let adapter = get_path(props, "Adapter").and_then(|adapter| {
+        bluez
+            .objects_with("org.bluez.Adapter1")
+            .into_iter()
+            .find(|(path, _)| *path == adapter)
+            .and_then(|(_, props)| get_string(&props, "Address"))
+    });
+    let pin = adapter
+        .as_deref()
+        .and_then(wii_pin)
+        .or_else(|| get_string(props, "Address").as_deref().and_then(wii_pin))
+        .ok_or_else(|| {
+            zbus::Error::Failure(
+                "The Wii PIN for this adapter can't be sent over D-Bus. Enable BlueZ's wiimote \
+                 plugin to pair this controller."
+                    .into(),
+            )
+        })?;
+
+    let conn = bluez.connection();
+    let agent = WiiPinAgent {
+        device: OwnedObjectPath::from(dev.path().to_owned()),
+        pin,
+    };
+    conn.object_server().at(AGENT_PATH, agent).await?;
+
+    let result = register_and_pair(conn, dev).await;
+    if let Err(err) = conn
+        .object_server()
+        .remove::<WiiPinAgent, _>(AGENT_PATH)
+        .await
+    {
+        tracing::warn!("failed to remove the Wii pairing agent: {err}");
+    }
+    result
+}
+
+async fn register_and_pair(conn: &zbus::Connection, dev: &Proxy<'_>) -> zbus::Result<()> {
+    let manager = Proxy::new(conn, "org.bluez", "/org/bluez", "org.bluez.AgentManager1").await?;
+    let path = ObjectPath::try_from(AGENT_PATH)?;
+    match manager
+        .call_method("RegisterAgent", &(&path, "NoInputNoOutput"))
+        .await
+    {
+        Ok(_) => {}
+        Err(zbus::Error::MethodError(name, _, _))
+            if name.as_str() == "org.bluez.Error.AlreadyExists" => {}
+        Err(err) => return Err(err),
+    }
+
+    let result = dev.call_method("Pair", &()).await;
+    if let Err(err) = manager.call_method("UnregisterAgent", &(&path,)).await {
+        tracing::warn!("failed to unregister the Wii pairing agent: {err}");
+    }
+    result.map(|_| ())
+}
+
+fn wiimote_plugin_handles(props: &Properties) -> bool {
+    let known = get_string(props, "Name")
+        .is_some_and(|name| WIIMOTE_PLUGIN_NAMES.contains(&name.as_str()))
+        || get_string(props, "Modalias").is_some_and(|modalias| {
+            let modalias = modalias.to_lowercase();
+            WIIMOTE_PLUGIN_MODALIASES
+                .iter()
+                .any(|ids| modalias.contains(ids))
+        });
+    known && !wiimote_plugin_disabled()
+}
+
+// The plugin is built into bluetoothd and only missing when disabled on its command line.
+fn wiimote_plugin_disabled() -> bool {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return false;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !fs::read_to_string(path.join("comm")).is_ok_and(|comm| comm.trim() == "bluetoothd") {
+            continue;
+        }
+        let cmdline = fs::read(path.join("cmdline")).unwrap_or_default();
+        let args: Vec<_> = cmdline
+            .split(|byte| *byte == 0)
+            .map(String::from_utf8_lossy)
+            .collect();
+        return disables_wiimote(args.iter().map(|arg| arg.as_ref()));
+    }
+
+    false
+}
+
+fn disables_wiimote<'a>(mut args: impl Iterator<Item = &'a str>) -> bool {
+    while let Some(arg) = args.next() {
+        let plugins = if let Some(plugins) = arg.strip_prefix("--noplugin=") {
+            plugins.to_string()
+        } else if arg == "--noplugin" || arg == "-P" {
+            args.next().unwrap_or_default().to_string()
+        } else if let Some(plugins) = arg.strip_prefix("-P") {
+            plugins.to_string()
+        } else {
+            continue;
+        };
+
+        if plugins
+            .split([',', ' '])
+            .any(|plugin| plugin == "wiimote" || plugin == "*")
+        {
+            return true;
+        }
+    }
+    false
+}
+
+// BlueZ takes the PIN as a C string, so it only works when the reversed address bytes happen to
+// be valid UTF-8 without a NUL byte.
+fn wii_pin(address: &str) -> Option<String> {
+    let mut bytes = address
+        .split(':')
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    if bytes.len() != 6 {
+        return None;
+    }
+    bytes.reverse();
+    String::from_utf8(bytes)
+        .ok()
+        .filter(|pin| !pin.contains('\0'))
+}
+
+fn get_string(props: &Properties, key: &str) -> Option<String> {
+    props
+        .get(key)
+        .and_then(|value| String::try_from(value.clone()).ok())
+}
+
+fn get_path(props: &Properties, key: &str) -> Option<OwnedObjectPath> {
+    props
+        .get(key)
+        .cloned()
+        .and_then(|value: OwnedValue| value.try_into().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverses_address_bytes_into_pin() {
+        assert_eq!(wii_pin("41:42:43:44:45:46").as_deref(), Some("FEDCBA"));
+        assert_eq!(wii_pin("7a:79:78:31:32:33").as_deref(), Some("321xyz"));
+    }
+
+    #[test]
+    fn rejects_pins_that_cant_be_sent() {
+        // Not valid UTF-8 once reversed.
+        assert_eq!(wii_pin("00:1B:DC:0F:AA:FF"), None);
+        // Valid UTF-8, but BlueZ would cut the PIN short at the NUL byte.
+        assert_eq!(wii_pin("41:42:00:44:45:46"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_addresses() {
+        assert_eq!(wii_pin("41:42:43:44:45"), None);
+        assert_eq!(wii_pin("41:42:43:44:45:46:47"), None);
+        assert_eq!(wii_pin("41:42:43:44:45:GG"), None);
+        assert_eq!(wii_pin(""), None);
+    }
+
+    #[test]
+    fn detects_disabled_wiimote_plugin() {
+        let disabled = |args: &[&str]| disables_wiimote(args.iter().copied());
+
+        assert!(!disabled(&["/usr/libexec/bluetooth/bluetoothd"]));
+        assert!(!disabled(&["bluetoothd", "--noplugin=sap,avrcp"]));
+        assert!(disabled(&["bluetoothd", "--noplugin=sap,wiimote"]));
+        assert!(disabled(&["bluetoothd", "-P", "wiimote"]));
+        assert!(disabled(&["bluetoothd", "-Pwiimote"]));
+        assert!(disabled(&["bluetoothd", "--noplugin", "*"]));
+    }
+}
@@ -0,0 +1,25 @@
+use futures_util::{Stream, StreamExt, future};
+use zbus::Connection;
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+
+const BLUEZ: &str = "org.bluez";
+
+pub async fn bluez_available(conn: &Connection) -> zbus::Result<bool> {
+    let proxy = DBusProxy::new(conn).await?;
+    Ok(proxy.name_has_owner(BusName::try_from(BLUEZ)?).await?)
+}
+
+pub async fn receive_bluez_availability(
+    conn: &Connection,
+) -> zbus::Result<impl Stream<Item = bool> + use<>> {
+    let proxy = DBusProxy::new(conn).await?;
+    let changes = proxy
+        .receive_name_owner_changed_with_args(&[(0, BLUEZ)])
+        .await?;
+
+    Ok(changes.filter_map(|signal| {
+        let available = signal.args().ok().map(|args| args.new_owner().is_some());
+        future::ready(available)
+    }))
+}
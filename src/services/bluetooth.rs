@@ -1,53 +1,149 @@
+use super::Bluez;
+use super::agent::{is_wii_controller, pair_with_wii_pin};
+use super::compat::{ControllerModel, detect_model};
+use super::leds::has_player_leds;
+use std::collections::HashMap;
 use std::fs;
 use std::io;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use zbus::names::InterfaceName;
 use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
-use zbus::{Connection, Proxy, fdo::ObjectManagerProxy, fdo::PropertiesProxy};
+use zbus::{Connection, Proxy, fdo::PropertiesProxy};
+
+// HID and HID-over-GATT, which every Bluetooth gamepad exposes.
+const GAMEPAD_SERVICE_UUIDS: [&str; 2] = [
+    "00001124-0000-1000-8000-00805f9b34fb",
+    "00001812-0000-1000-8000-00805f9b34fb",
+];
+const DISCOVERY_RSSI_FLOOR: i16 = -90;
+const PAIRED_SIGNAL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default)]
+pub struct DeviceState {
+    pub alias: Option<String>,
+    pub trusted: bool,
+    pub blocked: bool,
+    pub wake_allowed: Option<bool>,
+    pub modalias: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListFilter {
+    pub adapter: Option<String>,
+    pub gamepads_only: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DiscoveryFilter {
+    pub transport: &'static str,
+    pub rssi: Option<i16>,
+    pub duplicate_data: bool,
+    // Wii Remotes don't advertise the HID service UUID, so searches that may look for one turn
+    // this off.
+    pub hid_only: bool,
+}
+
+impl Default for DiscoveryFilter {
+    fn default() -> Self {
+        Self {
+            transport: "auto",
+            rssi: Some(DISCOVERY_RSSI_FLOOR),
+            duplicate_data: true,
+            hid_only: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Discovery {
+    pub found: bool,
+    // Another client was already discovering, so our filter only narrowed a shared session.
+    pub shared: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearbyDevice {
+    pub addr: String,
+    pub name: Option<String>,
+    pub model: ControllerModel,
+    pub rssi: i16,
+}
+
+pub fn read_device_state(bluez: &Bluez, address: &str) -> Option<DeviceState> {
+    let (_path, dev) = bluez.device(address)?;
+
+    Some(DeviceState {
+        alias: get_string(&dev, "Alias"),
+        trusted: get_bool(&dev, "Trusted").unwrap_or(false),
+        blocked: get_bool(&dev, "Blocked").unwrap_or(false),
+        wake_allowed: get_bool(&dev, "WakeAllowed"),
+        modalias: get_string(&dev, "Modalias"),
+    })
+}
 
-pub async fn find_device_path(
-    conn: &Connection,
+#[tracing::instrument(level = "debug", skip(bluez, value))]
+pub async fn set_device_property(
+    bluez: &Bluez,
     address: &str,
-) -> zbus::Result<Option<OwnedObjectPath>> {
-    let om = ObjectManagerProxy::builder(conn)
+    name: &str,
+    value: Value<'_>,
+) -> zbus::Result<bool> {
+    let Some(path) = bluez.device_path(address) else {
+        return Ok(false);
+    };
+
+    let props = PropertiesProxy::builder(bluez.connection())
         .destination("org.bluez")?
-        .path("/")?
+        .path(path)?
         .build()
         .await?;
 
-    let objects = om.get_managed_objects().await?;
-
-    for (path, ifaces) in objects {
-        let Some(dev) = ifaces.get("org.bluez.Device1") else {
-            continue;
-        };
-
-        let addr = get_string(dev, "Address").unwrap_or_default();
-        if addr.eq_ignore_ascii_case(address) {
-            return Ok(Some(path));
-        }
-    }
+    let iface = InterfaceName::try_from("org.bluez.Device1")?;
+    props.set(iface, name, value).await?;
+    Ok(true)
+}
 
-    Ok(None)
+pub fn get_device_property(bluez: &Bluez, address: &str, name: &str) -> Option<OwnedValue> {
+    let (_path, dev) = bluez.device(address)?;
+    dev.get(name).cloned()
 }
 
-pub async fn pair_device(conn: &Connection, address: &str) -> zbus::Result<bool> {
-    let Some(path) = find_device_path(conn, address).await? else {
+#[tracing::instrument(level = "debug", skip(bluez))]
+pub async fn pair_device(bluez: &Bluez, address: &str) -> zbus::Result<bool> {
+    let Some((path, props)) = bluez.device(address) else {
         return Ok(false);
     };
 
-    let dev = Proxy::new(conn, "org.bluez", path, "org.bluez.Device1").await?;
+    let dev = Proxy::new(bluez.connection(), "org.bluez", path, "org.bluez.Device1").await?;
+
+    if is_wii_controller(&props) {
+        pair_with_wii_pin(bluez, &dev, &props).await?;
+    } else {
+        dev.call_method("Pair", &()).await?;
+    }
 
-    dev.call_method("Pair", &()).await?;
+    // Pair can return before the Paired change reaches the cache, and the steps after pairing
+    // (renaming in particular) look the bond up there.
+    let paired = bluez.wait_until(|bluez| {
+        let (_path, dev) = bluez.device(address)?;
+        get_bool(&dev, "Paired").filter(|paired| *paired)
+    });
+    if tokio::time::timeout(PAIRED_SIGNAL_TIMEOUT, paired)
+        .await
+        .is_err()
+    {
+        tracing::warn!("BlueZ hasn't reported {address} as paired yet");
+    }
     Ok(true)
 }
 
-pub async fn trust_device(conn: &Connection, address: &str, trusted: bool) -> zbus::Result<bool> {
-    let Some(path) = find_device_path(conn, address).await? else {
+#[tracing::instrument(level = "debug", skip(bluez))]
+pub async fn trust_device(bluez: &Bluez, address: &str, trusted: bool) -> zbus::Result<bool> {
+    let Some(path) = bluez.device_path(address) else {
         return Ok(false);
     };
 
-    let props = PropertiesProxy::builder(conn)
+    let props = PropertiesProxy::builder(bluez.connection())
         .destination("org.bluez")?
         .path(path)?
         .build()
@@ -58,97 +154,86 @@ pub async fn trust_device(conn: &Connection, address: &str, trusted: bool) -> zb
     Ok(true)
 }
 
-pub async fn connect_device(conn: &Connection, address: &str) -> zbus::Result<bool> {
-    let Some(path) = find_device_path(conn, address).await? else {
+#[tracing::instrument(level = "debug", skip(bluez))]
+pub async fn connect_device(bluez: &Bluez, address: &str) -> zbus::Result<bool> {
+    let Some(path) = bluez.device_path(address) else {
         return Ok(false);
     };
 
-    let dev = Proxy::new(conn, "org.bluez", path, "org.bluez.Device1").await?;
+    let dev = Proxy::new(bluez.connection(), "org.bluez", path, "org.bluez.Device1").await?;
 
     dev.call_method("Connect", &()).await?;
     Ok(true)
 }
 
-pub async fn disconnect_device(conn: &Connection, address: &str) -> zbus::Result<bool> {
-    let Some(path) = find_device_path(conn, address).await? else {
+#[tracing::instrument(level = "debug", skip(bluez))]
+pub async fn disconnect_device(bluez: &Bluez, address: &str) -> zbus::Result<bool> {
+    let Some(path) = bluez.device_path(address) else {
         return Ok(false);
     };
 
-    let dev = Proxy::new(conn, "org.bluez", path, "org.bluez.Device1").await?;
+    let dev = Proxy::new(bluez.connection(), "org.bluez", path, "org.bluez.Device1").await?;
 
     dev.call_method("Disconnect", &()).await?;
     Ok(true)
 }
 
-pub async fn remove_device(conn: &Connection, address: &str) -> zbus::Result<bool> {
-    let Some(dev_path) = find_device_path(conn, address).await? else {
+#[tracing::instrument(level = "debug", skip(bluez))]
+pub async fn remove_device(bluez: &Bluez, address: &str) -> zbus::Result<bool> {
+    let Some((dev_path, dev)) = bluez.device(address) else {
         return Ok(false);
     };
 
-    let props = PropertiesProxy::builder(conn)
-        .destination("org.bluez")?
-        .path(dev_path.clone())?
-        .build()
-        .await?;
+    let adapter: OwnedObjectPath = dev
+        .get("Adapter")
+        .cloned()
+        .and_then(|value| value.try_into().ok())
+        .ok_or_else(|| zbus::Error::Failure("Invalid Adapter property".into()))?;
 
-    let iface = InterfaceName::try_from("org.bluez.Device1")?;
-    let adapter: OwnedObjectPath = props
-        .get(iface, "Adapter")
-        .await?
-        .try_into()
-        .map_err(|_| zbus::Error::Failure("Invalid Adapter property".into()))?;
-
-    let adapter_proxy = Proxy::new(conn, "org.bluez", adapter, "org.bluez.Adapter1").await?;
+    let adapter_proxy = Proxy::new(
+        bluez.connection(),
+        "org.bluez",
+        adapter,
+        "org.bluez.Adapter1",
+    )
+    .await?;
 
     adapter_proxy
-        .call_method("RemoveDevice", &(dev_path))
+        .call_method("RemoveDevice", &(&dev_path))
         .await?;
+    bluez.forget(&dev_path);
     Ok(true)
 }
 
+#[tracing::instrument(level = "debug", skip(bluez))]
 pub async fn rename_paired_device(
-    conn: &Connection,
+    bluez: &Bluez,
     address: &str,
     new_alias: &str,
 ) -> zbus::Result<bool> {
-    let om = ObjectManagerProxy::builder(conn)
+    let Some((path, dev)) = bluez.device(address) else {
+        return Ok(false);
+    };
+    if !get_bool(&dev, "Paired").unwrap_or(false) {
+        return Ok(false);
+    }
+
+    let props = PropertiesProxy::builder(bluez.connection())
         .destination("org.bluez")?
-        .path("/")?
+        .path(path)?
         .build()
         .await?;
+    let iface = InterfaceName::try_from("org.bluez.Device1")?;
 
-    let objects = om.get_managed_objects().await?;
-
-    for (path, ifaces) in objects {
-        let Some(dev) = ifaces.get("org.bluez.Device1") else {
-            continue;
-        };
-
-        let addr = get_string(dev, "Address").unwrap_or_default();
-        let paired = get_bool(dev, "Paired").unwrap_or(false);
-
-        if paired && addr.eq_ignore_ascii_case(address) {
-            let props = PropertiesProxy::builder(conn)
-                .destination("org.bluez")?
-                .path(path)?
-                .build()
-                .await?;
-            let iface = InterfaceName::try_from("org.bluez.Device1")?;
-
-            props.set(iface, "Alias", Value::from(new_alias)).await?;
-
-            return Ok(true);
-        }
-    }
-
-    Ok(false)
+    props.set(iface, "Alias", Value::from(new_alias)).await?;
+    Ok(true)
 }
 
-fn get_bool(props: &std::collections::HashMap<String, OwnedValue>, key: &str) -> Option<bool> {
+fn get_bool(props: &HashMap<String, OwnedValue>, key: &str) -> Option<bool> {
     props.get(key).cloned().and_then(|v| v.try_into().ok())
 }
 
-fn get_string(props: &std::collections::HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+fn get_string(props: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
     props.get(key).cloned().and_then(|v| v.try_into().ok())
 }
 
@@ -174,107 +259,265 @@ pub fn read_ps_controller_battery(addr: &str) -> io::Result<Option<(String, u8)>
     Ok(None)
 }
 
-async fn list_devices_by<F>(
-    conn: &Connection,
-    mut predicate: F,
-) -> zbus::Result<Vec<(String, String)>>
+fn list_devices_by<F>(bluez: &Bluez, filter: &ListFilter, mut predicate: F) -> Vec<(String, String)>
 where
     F: FnMut(bool, bool) -> bool,
 {
-    let om = ObjectManagerProxy::builder(conn)
-        .destination("org.bluez")?
-        .path("/")?
-        .build()
-        .await?;
-
-    let objects = om.get_managed_objects().await?;
     let mut devices = Vec::new();
 
-    for (_path, ifaces) in objects {
-        let Some(dev) = ifaces.get("org.bluez.Device1") else {
+    for (path, dev) in bluez.objects_with("org.bluez.Device1") {
+        if let Some(adapter) = &filter.adapter
+            && !path.as_str().starts_with(&format!("/org/bluez/{adapter}/"))
+        {
             continue;
-        };
+        }
+
+        if filter.gamepads_only && get_string(&dev, "Icon").as_deref() != Some("input-gaming") {
+            continue;
+        }
 
-        let connected = get_bool(dev, "Connected").unwrap_or(false);
-        let paired = get_bool(dev, "Paired").unwrap_or(false);
+        let connected = get_bool(&dev, "Connected").unwrap_or(false);
+        let paired = get_bool(&dev, "Paired").unwrap_or(false);
 
         if !predicate(connected, paired) {
             continue;
         }
 
-        let addr = get_string(dev, "Address").unwrap_or_else(|| "<unknown>".into());
-        let name = get_string(dev, "Alias")
-            .or_else(|| get_string(dev, "Name"))
+        let addr = get_string(&dev, "Address").unwrap_or_else(|| "<unknown>".into());
+        let name = get_string(&dev, "Alias")
+            .or_else(|| get_string(&dev, "Name"))
             .unwrap_or_else(|| "<unnamed>".into());
 
         devices.push((addr, name));
     }
 
-    Ok(devices)
+    devices
 }
 
-pub async fn list_connected_devices(conn: &Connection) -> zbus::Result<Vec<(String, String)>> {
-    list_devices_by(conn, |connected, _paired| connected).await
+pub fn list_connected_devices(bluez: &Bluez, filter: &ListFilter) -> Vec<(String, String)> {
+    list_devices_by(bluez, filter, |connected, _paired| connected)
 }
 
-pub async fn list_paired_devices(conn: &Connection) -> zbus::Result<Vec<(String, String)>> {
-    list_devices_by(conn, |_connected, paired| paired).await
+pub fn list_paired_devices(bluez: &Bluez, filter: &ListFilter) -> Vec<(String, String)> {
+    list_devices_by(bluez, filter, |_connected, paired| paired)
 }
 
-async fn get_default_adapter_path(conn: &Connection) -> zbus::Result<Option<OwnedObjectPath>> {
-    let om = ObjectManagerProxy::builder(conn)
-        .destination("org.bluez")?
-        .path("/")?
-        .build()
-        .await?;
+// Headsets, mice and keyboards don't get a player slot, so only devices that look like a game
+// controller in any of these ways count.
+pub fn is_game_controller(bluez: &Bluez, addr: &str) -> bool {
+    let Some((_path, dev)) = bluez.device(addr) else {
+        return false;
+    };
+    let name = get_string(&dev, "Alias")
+        .or_else(|| get_string(&dev, "Name"))
+        .unwrap_or_default();
+
+    get_string(&dev, "Icon").as_deref() == Some("input-gaming")
+        || detect_model(get_string(&dev, "Modalias").as_deref(), &name) != ControllerModel::Other
+        || has_player_leds(addr)
+}
+
+pub fn list_adapters(bluez: &Bluez) -> Vec<(String, String)> {
+    let mut adapters = Vec::new();
+
+    for (path, adapter) in bluez.objects_with("org.bluez.Adapter1") {
+        let Some(name) = path.as_str().rsplit('/').next() else {
+            continue;
+        };
+        let alias = get_string(&adapter, "Alias")
+            .or_else(|| get_string(&adapter, "Name"))
+            .unwrap_or_default();
+
+        adapters.push((name.to_string(), alias));
+    }
+
+    adapters.sort();
+    adapters
+}
 
-    let objects = om.get_managed_objects().await?;
+fn get_adapter_path(bluez: &Bluez, adapter: Option<&str>) -> Option<OwnedObjectPath> {
+    bluez
+        .objects_with("org.bluez.Adapter1")
+        .into_iter()
+        .map(|(path, _)| path)
+        .find(|path| adapter.is_none_or(|name| path.as_str().rsplit('/').next() == Some(name)))
+}
 
-    for (path, ifaces) in objects {
-        if ifaces.contains_key("org.bluez.Adapter1") {
-            return Ok(Some(path));
+#[tracing::instrument(level = "debug", skip(bluez))]
+pub async fn set_discovering(
+    bluez: &Bluez,
+    adapter: Option<&str>,
+    discovering: bool,
+) -> zbus::Result<bool> {
+    let Some(adapter_path) = get_adapter_path(bluez, adapter) else {
+        return Ok(false);
+    };
+
+    let adapter_proxy = Proxy::new(
+        bluez.connection(),
+        "org.bluez",
+        adapter_path,
+        "org.bluez.Adapter1",
+    )
+    .await?;
+    let method = if discovering {
+        "StartDiscovery"
+    } else {
+        "StopDiscovery"
+    };
+    adapter_proxy.call_method(method, &()).await?;
+    Ok(true)
+}
+
+// Unpaired gamepads seen by the current discovery, strongest signal first. BlueZ drops RSSI once a
+// device stops advertising, so stale cache entries from earlier scans don't show up.
+pub fn list_nearby_gamepads(bluez: &Bluez, adapter: Option<&str>) -> Vec<NearbyDevice> {
+    let mut devices = Vec::new();
+
+    for (path, dev) in bluez.objects_with("org.bluez.Device1") {
+        if let Some(adapter) = adapter
+            && !path.as_str().starts_with(&format!("/org/bluez/{adapter}/"))
+        {
+            continue;
+        }
+        if get_bool(&dev, "Paired").unwrap_or(false) {
+            continue;
         }
+        let Some(rssi) = dev.get("RSSI").cloned().and_then(|v| i16::try_from(v).ok()) else {
+            continue;
+        };
+
+        let name = get_string(&dev, "Name");
+        let model = detect_model(
+            get_string(&dev, "Modalias").as_deref(),
+            name.as_deref().unwrap_or_default(),
+        );
+        if model == ControllerModel::Other
+            && get_string(&dev, "Icon").as_deref() != Some("input-gaming")
+        {
+            continue;
+        }
+
+        devices.push(NearbyDevice {
+            addr: get_string(&dev, "Address").unwrap_or_else(|| "<unknown>".into()),
+            name,
+            model,
+            rssi,
+        });
     }
 
-    Ok(None)
+    devices.sort_by(|a, b| b.rssi.cmp(&a.rssi).then_with(|| a.addr.cmp(&b.addr)));
+    devices
 }
 
+#[tracing::instrument(level = "debug", skip(bluez, filter))]
+pub async fn start_discovery(
+    bluez: &Bluez,
+    adapter: Option<&str>,
+    filter: &DiscoveryFilter,
+) -> zbus::Result<bool> {
+    let Some(adapter_path) = get_adapter_path(bluez, adapter) else {
+        return Ok(false);
+    };
+
+    let adapter_proxy = Proxy::new(
+        bluez.connection(),
+        "org.bluez",
+        adapter_path,
+        "org.bluez.Adapter1",
+    )
+    .await?;
+    start_filtered_discovery(&adapter_proxy, filter).await
+}
+
+#[tracing::instrument(level = "debug", skip(bluez, filter))]
 pub async fn discover_device_by_address(
-    conn: &Connection,
+    bluez: &Bluez,
     address: &str,
+    adapter: Option<&str>,
+    filter: &DiscoveryFilter,
     timeout: Duration,
-) -> zbus::Result<bool> {
-    if find_device_path(conn, address).await?.is_some() {
-        return Ok(true);
+) -> zbus::Result<Discovery> {
+    if bluez.device_path(address).is_some() {
+        return Ok(Discovery {
+            found: true,
+            shared: false,
+        });
     }
 
-    let Some(adapter_path) = get_default_adapter_path(conn).await? else {
-        return Ok(false);
+    let Some(adapter_path) = get_adapter_path(bluez, adapter) else {
+        return Ok(Discovery::default());
     };
+    let already_discovering = bluez
+        .objects_with("org.bluez.Adapter1")
+        .into_iter()
+        .find(|(path, _)| *path == adapter_path)
+        .and_then(|(_, props)| get_bool(&props, "Discovering"))
+        .unwrap_or(false);
+
+    // BlueZ keeps one discovery session per D-Bus client, so concurrent reconnects each need their
+    // own connection to avoid stopping each other's discovery.
+    let session = bluez.with_connection(Connection::system().await?);
+    let adapter_proxy = Proxy::new(
+        session.connection(),
+        "org.bluez",
+        adapter_path,
+        "org.bluez.Adapter1",
+    )
+    .await?;
+    let started = start_filtered_discovery(&adapter_proxy, filter).await?;
+    let shared = already_discovering || !started;
+    if shared {
+        tracing::info!("another client is already discovering on this adapter");
+    }
 
-    let adapter_proxy = Proxy::new(conn, "org.bluez", adapter_path, "org.bluez.Adapter1").await?;
-    adapter_proxy.call_method("StartDiscovery", &()).await?;
+    let found = session.wait_for_device(address, timeout).await.is_some();
+    if started && let Err(err) = adapter_proxy.call_method("StopDiscovery", &()).await {
+        // Dropping the connection ends the session anyway.
+        tracing::warn!("failed to stop discovery: {err}");
+    }
+    Ok(Discovery { found, shared })
+}
 
-    let start = Instant::now();
-    let mut last_printed = None;
-    loop {
-        if find_device_path(conn, address).await?.is_some() {
-            adapter_proxy.call_method("StopDiscovery", &()).await?;
-            return Ok(true);
-        }
+// Returns false when this connection already has a discovery session running, which BlueZ
+// reports as InProgress or Busy depending on the version.
+async fn start_filtered_discovery(
+    adapter: &Proxy<'_>,
+    filter: &DiscoveryFilter,
+) -> zbus::Result<bool> {
+    let mut options: HashMap<&str, Value> = HashMap::new();
+    options.insert("Transport", Value::from(filter.transport));
+    if filter.hid_only {
+        options.insert(
+            "UUIDs",
+            Value::from(
+                GAMEPAD_SERVICE_UUIDS
+                    .iter()
+                    .map(|uuid| uuid.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+        );
+    }
+    if let Some(rssi) = filter.rssi {
+        options.insert("RSSI", Value::from(rssi));
+    }
+    options.insert("DuplicateData", Value::from(filter.duplicate_data));
 
-        let elapsed = start.elapsed();
-        if elapsed >= timeout {
-            adapter_proxy.call_method("StopDiscovery", &()).await?;
-            return Ok(false);
-        }
+    // Older adapters reject some filter keys; discovering unfiltered is still better than failing.
+    if let Err(err) = adapter.call_method("SetDiscoveryFilter", &options).await {
+        tracing::warn!("failed to set the discovery filter: {err}");
+    }
 
-        let remaining_secs = timeout.saturating_sub(elapsed).as_secs();
-        if last_printed != Some(remaining_secs) {
-            println!("Discovery: {remaining_secs}s left...");
-            last_printed = Some(remaining_secs);
+    match adapter.call_method("StartDiscovery", &()).await {
+        Ok(_) => Ok(true),
+        Err(zbus::Error::MethodError(name, _, _))
+            if matches!(
+                name.as_str(),
+                "org.bluez.Error.InProgress" | "org.bluez.Error.Busy"
+            ) =>
+        {
+            Ok(false)
         }
-
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        Err(err) => Err(err),
     }
 }
@@ -0,0 +1,257 @@
+use super::{bluez_available, receive_bluez_availability};
+use futures_util::{StreamExt, future, stream};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{Notify, watch};
+use zbus::message::Type;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::{Connection, MatchRule, Message, MessageStream, fdo::ObjectManagerProxy};
+
+// Signals can arrive in bursts while discovering, and nothing else can be received on the
+// connection while this queue is full.
+const SIGNAL_QUEUE: usize = 1024;
+
+pub type Properties = HashMap<String, OwnedValue>;
+type Objects = HashMap<OwnedObjectPath, HashMap<String, Properties>>;
+
+enum Update {
+    Signal(Message),
+    Owner(bool),
+}
+
+// One system bus connection shared by the whole applet, with a copy of the BlueZ object tree that
+// is kept current from InterfacesAdded/InterfacesRemoved/PropertiesChanged, so looking up a device
+// never needs a GetManagedObjects round trip.
+#[derive(Debug, Clone)]
+pub struct Bluez {
+    conn: Connection,
+    objects: Arc<RwLock<Objects>>,
+    changed: Arc<Notify>,
+    available: watch::Receiver<bool>,
+}
+
+impl Bluez {
+    pub async fn connect() -> zbus::Result<Self> {
+        let conn = Connection::system().await?;
+
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .sender("org.bluez")?
+            .build();
+        let signals = MessageStream::for_match_rule(rule, &conn, Some(SIGNAL_QUEUE)).await?;
+        let owners = receive_bluez_availability(&conn).await?;
+
+        let objects = Arc::new(RwLock::new(Objects::new()));
+        let (sender, available) = watch::channel(false);
+        let bluez = Self {
+            conn,
+            objects,
+            changed: Arc::new(Notify::new()),
+            available,
+        };
+
+        // Subscribed before the first sync, so nothing that changes in between is lost.
+        if bluez_available(&bluez.conn).await? {
+            sender.send_replace(bluez.sync().await);
+        }
+
+        let mut updates = stream::select(
+            signals.filter_map(|msg| future::ready(msg.ok().map(Update::Signal))),
+            owners.map(Update::Owner),
+        );
+        let watcher = bluez.clone();
+        tokio::spawn(async move {
+            while let Some(update) = updates.next().await {
+                match update {
+                    Update::Signal(msg) => watcher.apply(&msg),
+                    Update::Owner(true) => {
+                        sender.send_replace(watcher.sync().await);
+                    }
+                    Update::Owner(false) => {
+                        watcher.write().clear();
+                        sender.send_replace(false);
+                    }
+                }
+            }
+        });
+
+        Ok(bluez)
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    // Shares the object tree but sends calls over `conn`, for callers whose BlueZ session (like
+    // discovery) should end together with their own connection.
+    pub fn with_connection(&self, conn: Connection) -> Self {
+        Self {
+            conn,
+            ..self.clone()
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        *self.available.borrow()
+    }
+
+    pub fn availability(&self) -> watch::Receiver<bool> {
+        self.available.clone()
+    }
+
+    pub fn objects_with(&self, iface: &str) -> Vec<(OwnedObjectPath, Properties)> {
+        let mut found: Vec<_> = self
+            .read()
+            .iter()
+            .filter_map(|(path, ifaces)| Some((path.clone(), ifaces.get(iface)?.clone())))
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        found
+    }
+
+    pub fn device(&self, address: &str) -> Option<(OwnedObjectPath, Properties)> {
+        self.read().iter().find_map(|(path, ifaces)| {
+            let dev = ifaces.get("org.bluez.Device1")?;
+            let addr = dev
+                .get("Address")
+                .and_then(|value| String::try_from(value.clone()).ok())?;
+            addr.eq_ignore_ascii_case(address)
+                .then(|| (path.clone(), dev.clone()))
+        })
+    }
+
+    pub fn device_path(&self, address: &str) -> Option<OwnedObjectPath> {
+        self.device(address).map(|(path, _)| path)
+    }
+
+    pub async fn wait_for_device(
+        &self,
+        address: &str,
+        timeout: Duration,
+    ) -> Option<OwnedObjectPath> {
+        let found = self.wait_until(|bluez| bluez.device_path(address));
+        tokio::time::timeout(timeout, found).await.ok()
+    }
+
+    // Checks again after every change to the object tree until `check` finds something.
+    pub async fn wait_until<T>(&self, mut check: impl FnMut(&Self) -> Option<T>) -> T {
+        loop {
+            // Registered before looking, so a change in between still wakes us up.
+            let changed = self.changed.notified();
+            if let Some(found) = check(self) {
+                return found;
+            }
+            changed.await;
+        }
+    }
+
+    // Drops an object right away instead of waiting for InterfacesRemoved, so a lookup made just
+    // after removing a device can't find it anymore.
+    pub(crate) fn forget(&self, path: &OwnedObjectPath) {
+        self.write().remove(path);
+    }
+
+    async fn sync(&self) -> bool {
+        match self.managed_objects().await {
+            Ok(objects) => {
+                *self.write() = objects;
+                self.changed.notify_waiters();
+                true
+            }
+            Err(err) => {
+                tracing::warn!("failed to read the BlueZ object tree: {err}");
+                self.write().clear();
+                false
+            }
+        }
+    }
+
+    async fn managed_objects(&self) -> zbus::Result<Objects> {
+        let om = ObjectManagerProxy::builder(&self.conn)
+            .destination("org.bluez")?
+            .path("/")?
+            .build()
+            .await?;
+
+        Ok(om
+            .get_managed_objects()
+            .await?
+            .into_iter()
+            .map(|(path, ifaces)| {
+                let ifaces = ifaces
+                    .into_iter()
+                    .map(|(iface, props)| (iface.to_string(), props))
+                    .collect();
+                (path, ifaces)
+            })
+            .collect())
+    }
+
+    fn apply(&self, msg: &Message) {
+        let header = msg.header();
+        let Some(member) = header.member() else {
+            return;
+        };
+
+        match member.as_str() {
+            "InterfacesAdded" => {
+                let Ok((path, added)) = msg
+                    .body()
+                    .deserialize::<(OwnedObjectPath, HashMap<String, Properties>)>()
+                else {
+                    return;
+                };
+                self.write().entry(path).or_default().extend(added);
+            }
+            "InterfacesRemoved" => {
+                let Ok((path, removed)) =
+                    msg.body().deserialize::<(OwnedObjectPath, Vec<String>)>()
+                else {
+                    return;
+                };
+                let mut objects = self.write();
+                if let Some(ifaces) = objects.get_mut(&path) {
+                    ifaces.retain(|iface, _| !removed.contains(iface));
+                    if ifaces.is_empty() {
+                        objects.remove(&path);
+                    }
+                }
+            }
+            "PropertiesChanged" => {
+                let Some(path) = header.path() else {
+                    return;
+                };
+                let Ok((iface, changed, invalidated)) = msg
+                    .body()
+                    .deserialize::<(String, Properties, Vec<String>)>()
+                else {
+                    return;
+                };
+
+                let path = OwnedObjectPath::from(path.to_owned());
+                let mut objects = self.write();
+                let Some(props) = objects
+                    .get_mut(&path)
+                    .and_then(|ifaces| ifaces.get_mut(&iface))
+                else {
+                    return;
+                };
+                for name in invalidated {
+                    props.remove(&name);
+                }
+                props.extend(changed);
+            }
+            _ => return,
+        }
+        self.changed.notify_waiters();
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Objects> {
+        self.objects.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Objects> {
+        self.objects.write().unwrap_or_else(|err| err.into_inner())
+    }
+}
@@ -0,0 +1,475 @@
+use super::Bluez;
+use crate::fixes::HelperFix;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use tokio::process::Command;
+use zbus::zvariant::OwnedValue;
+
+const INPUT_CONF: &str = "/etc/bluetooth/input.conf";
+const MAIN_CONF: &str = "/etc/bluetooth/main.conf";
+const HELPER_PATH: &str = "/usr/libexec/cosmic-bluetooth-gamepad-helper";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerModel {
+    DualShock3,
+    DualShock4,
+    DualSense,
+    Xbox,
+    SwitchPro,
+    JoyCon,
+    Wiimote,
+    EightBitDo,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finding {
+    ClassicBondedOnly,
+    UhidMissing,
+    FastConnectable,
+    JustWorksRepairing,
+    ErtmEnabled,
+    XpadneoMissing,
+    HidNintendoMissing,
+}
+
+impl Finding {
+    pub fn helper_fix(self) -> Option<HelperFix> {
+        match self {
+            Finding::ClassicBondedOnly => Some(HelperFix::ClassicBondedOnlyOff),
+            Finding::FastConnectable => Some(HelperFix::FastConnectableOn),
+            Finding::JustWorksRepairing => Some(HelperFix::JustWorksRepairingAlways),
+            Finding::ErtmEnabled => Some(HelperFix::DisableErtm),
+            Finding::UhidMissing | Finding::XpadneoMissing | Finding::HidNintendoMissing => None,
+        }
+    }
+
+    // Turning ClassicBondedOnly off lets unbonded devices open HID channels, which is how
+    // CVE-2023-45866 injects keystrokes.
+    pub fn lowers_security(self) -> bool {
+        self == Finding::ClassicBondedOnly
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ControllerReport {
+    pub addr: String,
+    pub name: String,
+    pub model: ControllerModel,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Debug)]
+struct SystemSetup {
+    classic_bonded_only: bool,
+    userspace_hid: bool,
+    uhid_available: bool,
+    fast_connectable: bool,
+    just_works_repairing: String,
+    ertm_enabled: bool,
+    xpadneo_loaded: bool,
+    hid_nintendo_loaded: bool,
+}
+
+impl SystemSetup {
+    fn read() -> Self {
+        let input = read_ini(Path::new(INPUT_CONF));
+        let main = read_ini(Path::new(MAIN_CONF));
+        let setting = |ini: &Ini, section: &str, key: &str| {
+            ini.get(&(section.to_string(), key.to_string())).cloned()
+        };
+
+        Self {
+            // BlueZ defaults, used when the key is absent or commented out.
+            classic_bonded_only: setting(&input, "General", "ClassicBondedOnly")
+                .is_none_or(|value| parse_bool(&value)),
+            userspace_hid: setting(&input, "General", "UserspaceHID")
+                .is_none_or(|value| parse_bool(&value)),
+            uhid_available: Path::new("/dev/uhid").exists()
+                || Path::new("/sys/module/uhid").exists(),
+            fast_connectable: setting(&main, "General", "FastConnectable")
+                .is_some_and(|value| parse_bool(&value)),
+            just_works_repairing: setting(&main, "General", "JustWorksRepairing")
+                .unwrap_or_else(|| "never".to_string())
+                .to_lowercase(),
+            ertm_enabled: fs::read_to_string("/sys/module/bluetooth/parameters/disable_ertm")
+                .is_ok_and(|value| !parse_bool(value.trim())),
+            xpadneo_loaded: Path::new("/sys/module/hid_xpadneo").exists(),
+            hid_nintendo_loaded: Path::new("/sys/module/hid_nintendo").exists(),
+        }
+    }
+
+    fn findings(&self, model: ControllerModel) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        if model == ControllerModel::DualShock3 && self.classic_bonded_only {
+            findings.push(Finding::ClassicBondedOnly);
+        }
+        if self.userspace_hid && !self.uhid_available {
+            findings.push(Finding::UhidMissing);
+        }
+        // Xbox controllers give up paging the host quickly, so they miss the slower default page
+        // scan and fail to reconnect.
+        if model == ControllerModel::Xbox && !self.fast_connectable {
+            findings.push(Finding::FastConnectable);
+        }
+        if matches!(
+            model,
+            ControllerModel::Xbox | ControllerModel::SwitchPro | ControllerModel::JoyCon
+        ) && self.just_works_repairing == "never"
+        {
+            findings.push(Finding::JustWorksRepairing);
+        }
+        if model == ControllerModel::Xbox {
+            if self.ertm_enabled {
+                findings.push(Finding::ErtmEnabled);
+            }
+            if !self.xpadneo_loaded {
+                findings.push(Finding::XpadneoMissing);
+            }
+        }
+        if matches!(model, ControllerModel::SwitchPro | ControllerModel::JoyCon)
+            && !self.hid_nintendo_loaded
+        {
+            findings.push(Finding::HidNintendoMissing);
+        }
+
+        findings
+    }
+}
+
+pub fn check_compatibility(bluez: &Bluez) -> Vec<ControllerReport> {
+    let setup = SystemSetup::read();
+    let mut reports = Vec::new();
+
+    for (_path, dev) in bluez.objects_with("org.bluez.Device1") {
+        let paired = get_bool(&dev, "Paired");
+        let connected = get_bool(&dev, "Connected");
+        if !paired && !connected {
+            continue;
+        }
+
+        let name = get_string(&dev, "Alias")
+            .or_else(|| get_string(&dev, "Name"))
+            .unwrap_or_default();
+        let model = detect_model(get_string(&dev, "Modalias").as_deref(), &name);
+        if model == ControllerModel::Other
+            && get_string(&dev, "Icon").as_deref() != Some("input-gaming")
+        {
+            continue;
+        }
+
+        reports.push(ControllerReport {
+            addr: get_string(&dev, "Address").unwrap_or_default(),
+            name,
+            model,
+            findings: setup.findings(model),
+        });
+    }
+
+    reports.sort_by(|a, b| a.addr.cmp(&b.addr));
+    reports
+}
+
+pub async fn apply_fix(fix: HelperFix) -> io::Result<()> {
+    run_helper(fix.name()).await
+}
+
+pub async fn restart_bluetooth() -> io::Result<()> {
+    run_helper("--restart-bluetooth").await
+}
+
+async fn run_helper(arg: &str) -> io::Result<()> {
+    let output = Command::new("pkexec")
+        .args([HELPER_PATH, arg])
+        .output()
+        .await?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+pub fn detect_model(modalias: Option<&str>, name: &str) -> ControllerModel {
+    if let Some((vendor, product)) = modalias.and_then(parse_modalias) {
+        match (vendor, product) {
+            (0x054c, 0x0268) => return ControllerModel::DualShock3,
+            (0x054c, 0x05c4 | 0x09cc) => return ControllerModel::DualShock4,
+            (0x054c, 0x0ce6 | 0x0df2) => return ControllerModel::DualSense,
+            (0x045e, 0x02e0 | 0x02fd | 0x0b05 | 0x0b13 | 0x0b20 | 0x0b21 | 0x0b22) => {
+                return ControllerModel::Xbox;
+            }
+            (0x057e, 0x2009) => return ControllerModel::SwitchPro,
+            (0x057e, 0x2006 | 0x2007) => return ControllerModel::JoyCon,
+            (0x057e, 0x0306 | 0x0330) => return ControllerModel::Wiimote,
+            (0x2dc8, _) => return ControllerModel::EightBitDo,
+            _ => {}
+        }
+    }
+
+    // Checked first, since some 8BitDo pads call themselves "Wireless Controller".
+    let name = name.to_lowercase();
+    if name.contains("8bitdo") {
+        ControllerModel::EightBitDo
+    } else if name.contains("playstation(r)3") || name.contains("sixaxis") {
+        ControllerModel::DualShock3
+    } else if name.contains("dualsense") {
+        ControllerModel::DualSense
+    } else if name.contains("wireless controller") && !name.contains("xbox") {
+        ControllerModel::DualShock4
+    } else if name.contains("xbox") {
+        ControllerModel::Xbox
+    } else if name.contains("pro controller") {
+        ControllerModel::SwitchPro
+    } else if name.contains("joy-con") {
+        ControllerModel::JoyCon
+    } else if name.contains("rvl-cnt") {
+        ControllerModel::Wiimote
+    } else {
+        ControllerModel::Other
+    }
+}
+
+// BlueZ reports e.g. `usb:v054Cp0268d0100` or `bluetooth:v045Ep0B13d0509`.
+fn parse_modalias(modalias: &str) -> Option<(u16, u16)> {
+    let (_, ids) = modalias.split_once(':')?;
+    let ids = ids.strip_prefix('v')?;
+    let vendor = u16::from_str_radix(ids.get(..4)?, 16).ok()?;
+    let product = u16::from_str_radix(ids.get(4..)?.strip_prefix('p')?.get(..4)?, 16).ok()?;
+    Some((vendor, product))
+}
+
+type Ini = HashMap<(String, String), String>;
+
+fn read_ini(path: &Path) -> Ini {
+    let mut ini = Ini::new();
+    let Ok(raw) = fs::read_to_string(path) else {
+        return ini;
+    };
+
+    let mut section = String::new();
+    for line in raw.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = name.trim().to_string();
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            ini.insert(
+                (section.clone(), key.trim().to_string()),
+                value.trim().to_string(),
+            );
+        }
+    }
+
+    ini
+}
+
+fn parse_bool(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "true" | "yes" | "on" | "1" | "y"
+    )
+}
+
+fn get_bool(props: &HashMap<String, OwnedValue>, key: &str) -> bool {
+    props
+        .get(key)
+        .and_then(|value| bool::try_from(value).ok())
+        .unwrap_or(false)
+}
+
+fn get_string(props: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    props
+        .get(key)
+        .and_then(|value| String::try_from(value.clone()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn setup() -> SystemSetup {
+        SystemSetup {
+            classic_bonded_only: false,
+            userspace_hid: true,
+            uhid_available: true,
+            fast_connectable: true,
+            just_works_repairing: "always".to_string(),
+            ertm_enabled: false,
+            xpadneo_loaded: true,
+            hid_nintendo_loaded: true,
+        }
+    }
+
+    #[test]
+    fn parses_modalias_ids() {
+        assert_eq!(
+            parse_modalias("usb:v054Cp0268d0100"),
+            Some((0x054c, 0x0268))
+        );
+        assert_eq!(
+            parse_modalias("bluetooth:v045Ep0B13d0509"),
+            Some((0x045e, 0x0b13))
+        );
+        assert_eq!(parse_modalias("usb:v054C"), None);
+        assert_eq!(parse_modalias("v054Cp0268d0100"), None);
+        assert_eq!(parse_modalias("usb:vZZZZp0268d0100"), None);
+    }
+
+    #[test]
+    fn detects_models_by_ids_before_names() {
+        let model = |modalias, name| detect_model(Some(modalias), name);
+
+        assert_eq!(
+            model("usb:v054Cp0268d0100", "PLAYSTATION(R)3 Controller"),
+            ControllerModel::DualShock3
+        );
+        assert_eq!(
+            model("usb:v054Cp0CE6d0100", "Wireless Controller"),
+            ControllerModel::DualSense
+        );
+        assert_eq!(
+            model("bluetooth:v045Ep0B13d0509", "Xbox Wireless Controller"),
+            ControllerModel::Xbox
+        );
+        assert_eq!(
+            model("bluetooth:v2DC8p6006d0001", "Wireless Controller"),
+            ControllerModel::EightBitDo
+        );
+        assert_eq!(
+            model("bluetooth:v057Ep0330d0001", "Nintendo RVL-CNT-01-UC"),
+            ControllerModel::Wiimote
+        );
+    }
+
+    #[test]
+    fn ignores_microsoft_mice_and_keyboards() {
+        assert_eq!(
+            detect_model(Some("bluetooth:v045Ep0915d0001"), "Surface Keyboard"),
+            ControllerModel::Other
+        );
+        assert_eq!(
+            detect_model(Some("usb:v045Ep082Fd0001"), "Microsoft Bluetooth Mouse"),
+            ControllerModel::Other
+        );
+    }
+
+    #[test]
+    fn detects_models_by_name() {
+        assert_eq!(
+            detect_model(None, "8BitDo Pro 2 Wireless Controller"),
+            ControllerModel::EightBitDo
+        );
+        assert_eq!(
+            detect_model(None, "Wireless Controller"),
+            ControllerModel::DualShock4
+        );
+        assert_eq!(
+            detect_model(None, "Xbox Wireless Controller"),
+            ControllerModel::Xbox
+        );
+        assert_eq!(detect_model(None, "Joy-Con (L)"), ControllerModel::JoyCon);
+        assert_eq!(detect_model(None, "MX Master 3"), ControllerModel::Other);
+    }
+
+    #[test]
+    fn reads_ini_sections_and_skips_comments() {
+        let path = env::temp_dir().join(format!(
+            "cosmic-bluetooth-gamepad-compat-{}.conf",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "# ClassicBondedOnly=false\nTopLevel = 1\n[General]\n  ClassicBondedOnly = false\n; FastConnectable=true\n[ Policy ]\nAutoEnable=true\n",
+        )
+        .unwrap();
+
+        let ini = read_ini(&path);
+        fs::remove_file(&path).unwrap();
+
+        let get = |section: &str, key: &str| {
+            ini.get(&(section.to_string(), key.to_string()))
+                .map(String::as_str)
+        };
+        assert_eq!(get("", "TopLevel"), Some("1"));
+        assert_eq!(get("General", "ClassicBondedOnly"), Some("false"));
+        assert_eq!(get("General", "FastConnectable"), None);
+        assert_eq!(get("Policy", "AutoEnable"), Some("true"));
+        assert_eq!(ini.len(), 3);
+
+        assert!(read_ini(Path::new("/nonexistent/input.conf")).is_empty());
+    }
+
+    #[test]
+    fn reports_nothing_on_a_fixed_setup() {
+        for model in [
+            ControllerModel::DualShock3,
+            ControllerModel::DualSense,
+            ControllerModel::Xbox,
+            ControllerModel::SwitchPro,
+            ControllerModel::Other,
+        ] {
+            assert!(setup().findings(model).is_empty(), "{model:?}");
+        }
+    }
+
+    #[test]
+    fn reports_findings_for_affected_models_only() {
+        let stock = SystemSetup {
+            classic_bonded_only: true,
+            uhid_available: false,
+            fast_connectable: false,
+            just_works_repairing: "never".to_string(),
+            ertm_enabled: true,
+            xpadneo_loaded: false,
+            hid_nintendo_loaded: false,
+            ..setup()
+        };
+
+        assert_eq!(
+            stock.findings(ControllerModel::DualShock3),
+            [Finding::ClassicBondedOnly, Finding::UhidMissing]
+        );
+        assert_eq!(
+            stock.findings(ControllerModel::DualSense),
+            [Finding::UhidMissing]
+        );
+        assert_eq!(
+            stock.findings(ControllerModel::Xbox),
+            [
+                Finding::UhidMissing,
+                Finding::FastConnectable,
+                Finding::JustWorksRepairing,
+                Finding::ErtmEnabled,
+                Finding::XpadneoMissing,
+            ]
+        );
+        assert_eq!(
+            stock.findings(ControllerModel::SwitchPro),
+            [
+                Finding::UhidMissing,
+                Finding::JustWorksRepairing,
+                Finding::HidNintendoMissing,
+            ]
+        );
+
+        let kernel_hid = SystemSetup {
+            userspace_hid: false,
+            ..stock
+        };
+        assert!(kernel_hid.findings(ControllerModel::DualSense).is_empty());
+    }
+}
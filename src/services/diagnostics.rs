@@ -0,0 +1,420 @@
+use super::Bluez;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use tokio::process::Command;
+use zbus::fdo::ObjectManagerProxy;
+use zbus::zvariant::OwnedValue;
+
+const BLUETOOTH_CONFIGS: [&str; 3] = [
+    "/etc/bluetooth/main.conf",
+    "/etc/bluetooth/input.conf",
+    "/etc/bluetooth/network.conf",
+];
+const JOURNAL_LINES: &str = "500";
+
+type Objects = Vec<(String, Vec<(String, HashMap<String, OwnedValue>)>)>;
+
+pub async fn collect_diagnostics(
+    bluez: Option<&Bluez>,
+    history: &[String],
+    anonymize: bool,
+) -> String {
+    let mut report = String::new();
+    let mut addresses = Vec::new();
+
+    let _ = writeln!(
+        report,
+        "# Applet\n{} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let _ = writeln!(report, "\n# BlueZ version");
+    let _ = writeln!(report, "{}", bluez_version().await);
+
+    let _ = writeln!(report, "\n# BlueZ objects");
+    match bluez_objects(bluez).await {
+        Ok(objects) => {
+            for (path, ifaces) in objects {
+                for (iface, props) in ifaces {
+                    if !matches!(
+                        iface.as_str(),
+                        "org.bluez.Adapter1" | "org.bluez.Device1" | "org.bluez.Battery1"
+                    ) {
+                        continue;
+                    }
+                    if iface == "org.bluez.Device1"
+                        && let Some(addr) = props
+                            .get("Address")
+                            .and_then(|value| String::try_from(value.clone()).ok())
+                    {
+                        addresses.push(addr);
+                    }
+
+                    let _ = writeln!(report, "\n[{path} {iface}]");
+                    let props: BTreeMap<_, _> = props.into_iter().collect();
+                    for (name, value) in props {
+                        // Adapters are named after the computer's hostname by default.
+                        if anonymize
+                            && iface == "org.bluez.Adapter1"
+                            && matches!(name.as_str(), "Name" | "Alias")
+                        {
+                            let _ = writeln!(report, "{name} = \"<hostname>\"");
+                            continue;
+                        }
+                        let _ = writeln!(report, "{name} = {}", &*value);
+                    }
+                }
+            }
+        }
+        Err(err) => {
+            let _ = writeln!(report, "unavailable: {err}");
+        }
+    }
+
+    let _ = writeln!(report, "\n# Kernel drivers and input nodes");
+    for addr in &addresses {
+        let _ = writeln!(report, "\n[{addr}]");
+        report.push_str(&driver_bindings(addr));
+    }
+
+    let _ = writeln!(report, "\n# Battery sources");
+    report.push_str(&battery_sources(&addresses));
+
+    let _ = writeln!(report, "\n# Bluetooth configuration");
+    for path in BLUETOOTH_CONFIGS {
+        let _ = writeln!(report, "\n[{path}]");
+        report.push_str(&effective_settings(Path::new(path)));
+    }
+
+    let _ = writeln!(report, "\n# Applet history");
+    for line in history {
+        let _ = writeln!(report, "{line}");
+    }
+
+    let _ = writeln!(report, "\n# bluetoothd journal");
+    report.push_str(&bluetoothd_journal(&addresses, anonymize).await);
+
+    if anonymize {
+        return anonymize_addresses(&report);
+    }
+    report
+}
+
+// Replaces every Bluetooth address, in both `AA:BB:..` and object path `AA_BB_..` form, with a
+// stable placeholder so reports can still be correlated without identifying the hardware.
+fn anonymize_addresses(text: &str) -> String {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut out = String::with_capacity(text.len());
+    let bytes = text.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(separator) = address_at(bytes, i) {
+            let key = text[i..i + 17].to_ascii_uppercase().replace('_', ":");
+            let next = seen.len() + 1;
+            let index = *seen.entry(key).or_insert(next);
+            let placeholder = format!("00:00:00:00:{:02X}:{:02X}", index >> 8, index & 0xff);
+            out.push_str(&placeholder.replace(':', &separator.to_string()));
+            i += 17;
+            continue;
+        }
+
+        let ch = text[i..].chars().next().unwrap_or_default();
+        out.push(ch);
+        i += ch.len_utf8().max(1);
+    }
+
+    out
+}
+
+fn address_at(bytes: &[u8], start: usize) -> Option<char> {
+    let candidate = bytes.get(start..start + 17)?;
+    let separator = candidate[2];
+    if separator != b':' && separator != b'_' {
+        return None;
+    }
+    if start > 0 && bytes[start - 1].is_ascii_hexdigit() {
+        return None;
+    }
+    if bytes.get(start + 17).is_some_and(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    let valid = candidate.iter().enumerate().all(|(i, byte)| {
+        if i % 3 == 2 {
+            *byte == separator
+        } else {
+            byte.is_ascii_hexdigit()
+        }
+    });
+    valid.then_some(separator as char)
+}
+
+// Asks BlueZ directly instead of using the cached tree, so the report shows what bluetoothd
+// actually has.
+async fn bluez_objects(bluez: Option<&Bluez>) -> zbus::Result<Objects> {
+    let bluez = bluez.ok_or_else(|| zbus::Error::Failure("no system bus connection".into()))?;
+    let om = ObjectManagerProxy::builder(bluez.connection())
+        .destination("org.bluez")?
+        .path("/")?
+        .build()
+        .await?;
+
+    let mut objects: Vec<_> = om
+        .get_managed_objects()
+        .await?
+        .into_iter()
+        .map(|(path, ifaces)| {
+            let ifaces = ifaces
+                .into_iter()
+                .map(|(iface, props)| (iface.to_string(), props))
+                .collect();
+            (path.to_string(), ifaces)
+        })
+        .collect();
+    objects.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(objects)
+}
+
+async fn bluez_version() -> String {
+    for (program, arg) in [("bluetoothctl", "--version"), ("bluetoothd", "--version")] {
+        if let Ok(output) = Command::new(program).arg(arg).output().await
+            && output.status.success()
+        {
+            return String::from_utf8_lossy(&output.stdout).trim().to_string();
+        }
+    }
+    "unknown".to_string()
+}
+
+fn driver_bindings(addr: &str) -> String {
+    let mut out = String::new();
+
+    if let Ok(entries) = fs::read_dir("/sys/bus/hid/devices") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let uevent = fs::read_to_string(path.join("uevent")).unwrap_or_default();
+            let matches = uevent.lines().any(|line| {
+                line.strip_prefix("HID_UNIQ=")
+                    .is_some_and(|uniq| uniq.eq_ignore_ascii_case(addr))
+            });
+            if !matches {
+                continue;
+            }
+
+            let driver = fs::read_link(path.join("driver"))
+                .ok()
+                .and_then(|link| {
+                    link.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                })
+                .unwrap_or_else(|| "none".to_string());
+            let _ = writeln!(
+                out,
+                "hid {} driver={driver}",
+                entry.file_name().to_string_lossy()
+            );
+            for line in uevent.lines().filter(|line| line.starts_with("HID_")) {
+                let _ = writeln!(out, "  {line}");
+            }
+        }
+    }
+
+    match super::list_input_nodes(addr) {
+        Ok(nodes) if nodes.is_empty() => {
+            let _ = writeln!(out, "no input nodes");
+        }
+        Ok(nodes) => {
+            for node in nodes {
+                let name = fs::read_to_string(node.path.join("name")).unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "input {} {:?} \"{}\"",
+                    node.path.display(),
+                    node.kind,
+                    name.trim()
+                );
+            }
+        }
+        Err(err) => {
+            let _ = writeln!(out, "input nodes unavailable: {err}");
+        }
+    }
+
+    out
+}
+
+fn battery_sources(addresses: &[String]) -> String {
+    let mut out = String::new();
+    let Ok(entries) = fs::read_dir("/sys/class/power_supply") else {
+        return "power_supply unavailable\n".to_string();
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !addresses
+            .iter()
+            .any(|addr| name.to_lowercase().contains(&addr.to_lowercase()))
+        {
+            continue;
+        }
+
+        let read = |attr: &str| {
+            fs::read_to_string(entry.path().join(attr))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_else(|_| "?".to_string())
+        };
+        let _ = writeln!(
+            out,
+            "{name} capacity={} status={} scope={}",
+            read("capacity"),
+            read("status"),
+            read("scope")
+        );
+    }
+
+    if out.is_empty() {
+        out.push_str("no kernel battery sources\n");
+    }
+    out
+}
+
+fn effective_settings(path: &Path) -> String {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) => return format!("unavailable: {err}\n"),
+    };
+
+    let mut out = String::new();
+    for line in raw.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        let _ = writeln!(out, "{line}");
+    }
+
+    if out.is_empty() {
+        out.push_str("defaults only\n");
+    }
+    out
+}
+
+async fn bluetoothd_journal(addresses: &[String], anonymize: bool) -> String {
+    let output = Command::new("journalctl")
+        .args([
+            "--unit=bluetooth.service",
+            "--no-pager",
+            "--output=short-iso",
+            "--lines",
+            JOURNAL_LINES,
+        ])
+        .output()
+        .await;
+
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            return format!(
+                "journalctl failed: {}\n",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Err(err) => return format!("journalctl unavailable: {err}\n"),
+    };
+
+    let needles: Vec<String> = addresses
+        .iter()
+        .flat_map(|addr| [addr.to_uppercase(), addr.to_uppercase().replace(':', "_")])
+        .collect();
+
+    let mut out = String::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let upper = line.to_uppercase();
+        if needles.iter().any(|needle| upper.contains(needle)) {
+            if anonymize {
+                let _ = writeln!(out, "{}", without_hostname(line));
+            } else {
+                let _ = writeln!(out, "{line}");
+            }
+        }
+    }
+
+    if out.is_empty() {
+        out.push_str("no lines mention the known devices\n");
+    }
+    out
+}
+
+// short-iso lines read `<timestamp> <hostname> <identifier>: <message>`. The hostname is the same
+// on every line and only identifies the machine, so anonymized reports drop it.
+fn without_hostname(line: &str) -> String {
+    let mut fields = line.splitn(3, ' ');
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(time), Some(_hostname), Some(rest))
+            if time.starts_with(|ch: char| ch.is_ascii_digit()) =>
+        {
+            format!("{time} {rest}")
+        }
+        _ => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymizes_colon_and_underscore_forms_alike() {
+        let text = "Address = AA:BB:CC:DD:EE:01\n[/org/bluez/hci0/dev_AA_BB_CC_DD_EE_01]";
+
+        assert_eq!(
+            anonymize_addresses(text),
+            "Address = 00:00:00:00:00:01\n[/org/bluez/hci0/dev_00_00_00_00_00_01]"
+        );
+    }
+
+    #[test]
+    fn anonymizes_lowercase_addresses() {
+        assert_eq!(
+            anonymize_addresses("uniq aa:bb:cc:dd:ee:01, then AA:BB:CC:DD:EE:01"),
+            "uniq 00:00:00:00:00:01, then 00:00:00:00:00:01"
+        );
+    }
+
+    #[test]
+    fn numbers_addresses_in_order_of_appearance() {
+        let text = "AA:BB:CC:DD:EE:02 AA:BB:CC:DD:EE:01 AA:BB:CC:DD:EE:02 AA:BB:CC:DD:EE:03";
+
+        assert_eq!(
+            anonymize_addresses(text),
+            "00:00:00:00:00:01 00:00:00:00:00:02 00:00:00:00:00:01 00:00:00:00:00:03"
+        );
+    }
+
+    #[test]
+    fn keeps_addresses_embedded_in_longer_hex_runs() {
+        let text = "0AA:BB:CC:DD:EE:01 AA:BB:CC:DD:EE:01F AA:BB:CC:DD:EE AA-BB-CC-DD-EE-01";
+
+        assert_eq!(anonymize_addresses(text), text);
+    }
+
+    #[test]
+    fn anonymizes_addresses_next_to_other_text() {
+        assert_eq!(
+            anonymize_addresses("hid-playstation 0005:054C:0CE6.0003: dev_AA_BB_CC_DD_EE_01/"),
+            "hid-playstation 0005:054C:0CE6.0003: dev_00_00_00_00_00_01/"
+        );
+    }
+
+    #[test]
+    fn drops_hostname_from_journal_lines() {
+        assert_eq!(
+            without_hostname("2026-10-19T12:00:00+0000 my-laptop bluetoothd[812]: Connected"),
+            "2026-10-19T12:00:00+0000 bluetoothd[812]: Connected"
+        );
+        assert_eq!(without_hostname("-- No entries --"), "-- No entries --");
+    }
+}
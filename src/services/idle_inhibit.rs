@@ -0,0 +1,63 @@
+use zbus::zvariant::OwnedFd;
+use zbus::{Connection, Proxy};
+
+const WHO: &str = "Cosmic Bluetooth Gamepad";
+
+#[derive(Debug)]
+pub enum IdleInhibitor {
+    ScreenSaver { conn: Connection, cookie: u32 },
+    Logind { _fd: OwnedFd },
+}
+
+impl IdleInhibitor {
+    pub async fn release(&self) -> zbus::Result<()> {
+        match self {
+            Self::ScreenSaver { conn, cookie } => {
+                let proxy = screensaver_proxy(conn).await?;
+                proxy.call_method("UnInhibit", cookie).await?;
+                Ok(())
+            }
+            Self::Logind { .. } => Ok(()),
+        }
+    }
+}
+
+pub async fn inhibit_idle(reason: &str) -> zbus::Result<IdleInhibitor> {
+    match inhibit_screensaver(reason).await {
+        Ok(inhibitor) => Ok(inhibitor),
+        Err(_) => inhibit_logind(reason).await,
+    }
+}
+
+async fn inhibit_screensaver(reason: &str) -> zbus::Result<IdleInhibitor> {
+    let conn = Connection::session().await?;
+    let proxy = screensaver_proxy(&conn).await?;
+    let cookie: u32 = proxy.call("Inhibit", &(WHO, reason)).await?;
+    Ok(IdleInhibitor::ScreenSaver { conn, cookie })
+}
+
+async fn inhibit_logind(reason: &str) -> zbus::Result<IdleInhibitor> {
+    let conn = Connection::system().await?;
+    let proxy = Proxy::new(
+        &conn,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    )
+    .await?;
+
+    let fd: OwnedFd = proxy
+        .call("Inhibit", &("idle", WHO, reason, "block"))
+        .await?;
+    Ok(IdleInhibitor::Logind { _fd: fd })
+}
+
+async fn screensaver_proxy(conn: &Connection) -> zbus::Result<Proxy<'static>> {
+    Proxy::new(
+        conn,
+        "org.freedesktop.ScreenSaver",
+        "/org/freedesktop/ScreenSaver",
+        "org.freedesktop.ScreenSaver",
+    )
+    .await
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputNodeKind {
+    Gamepad,
+    Touchpad,
+    MotionSensors,
+}
+
+#[derive(Debug, Clone)]
+pub struct InputNode {
+    pub path: PathBuf,
+    pub kind: InputNodeKind,
+}
+
+pub fn list_input_nodes(addr: &str) -> io::Result<Vec<InputNode>> {
+    let mut nodes = Vec::new();
+
+    for entry in fs::read_dir("/sys/class/input")? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if !entry.file_name().to_string_lossy().starts_with("input") {
+            continue;
+        }
+
+        let uniq = fs::read_to_string(path.join("uniq")).unwrap_or_default();
+        if !uniq.trim().eq_ignore_ascii_case(addr) {
+            continue;
+        }
+
+        let name = fs::read_to_string(path.join("name")).unwrap_or_default();
+        let name = name.trim();
+        let kind = if name.ends_with("Touchpad") {
+            InputNodeKind::Touchpad
+        } else if name.ends_with("Motion Sensors") {
+            InputNodeKind::MotionSensors
+        } else {
+            InputNodeKind::Gamepad
+        };
+
+        nodes.push(InputNode { path, kind });
+    }
+
+    Ok(nodes)
+}
+
+pub fn set_input_inhibited(addr: &str, kind: InputNodeKind, inhibited: bool) -> io::Result<usize> {
+    let mut count = 0;
+
+    for node in list_input_nodes(addr)? {
+        if node.kind != kind {
+            continue;
+        }
+
+        fs::write(
+            node.path.join("inhibited"),
+            if inhibited { "1" } else { "0" },
+        )?;
+        count += 1;
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const PLAYER_SLOTS: u8 = 4;
+
+pub fn set_player_leds(addr: &str, slot: u8) -> io::Result<bool> {
+    let leds = find_player_leds(addr)?;
+    if leds.is_empty() {
+        return Ok(false);
+    }
+
+    let pattern = player_pattern(leds.len(), slot);
+    for ((_index, path), on) in leds.iter().zip(pattern) {
+        let brightness = if on {
+            fs::read_to_string(path.join("max_brightness"))
+                .ok()
+                .and_then(|value| value.trim().parse::<u32>().ok())
+                .unwrap_or(1)
+        } else {
+            0
+        };
+        fs::write(path.join("brightness"), brightness.to_string())?;
+    }
+
+    Ok(true)
+}
+
+pub fn has_player_leds(addr: &str) -> bool {
+    find_player_leds(addr).is_ok_and(|leds| !leds.is_empty())
+}
+
+fn find_player_leds(addr: &str) -> io::Result<Vec<(u8, PathBuf)>> {
+    let mut leds = Vec::new();
+
+    for entry in fs::read_dir("/sys/class/leds")? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        let Some(index) = name
+            .rsplit_once(":player-")
+            .and_then(|(_, index)| index.parse::<u8>().ok())
+        else {
+            continue;
+        };
+
+        if device_matches_address(&entry.path().join("device"), addr) {
+            leds.push((index, entry.path()));
+        }
+    }
+
+    leds.sort_by_key(|(index, _)| *index);
+    Ok(leds)
+}
+
+// DualSense has five LEDs and uses the same centered patterns as the kernel
+// driver; Switch style pads light the first `slot` LEDs.
+fn player_pattern(count: usize, slot: u8) -> Vec<bool> {
+    if count == 5 {
+        let pattern: [u8; 5] = match slot {
+            1 => [0, 0, 1, 0, 0],
+            2 => [0, 1, 0, 1, 0],
+            3 => [1, 0, 1, 0, 1],
+            4 => [1, 1, 0, 1, 1],
+            _ => [1, 1, 1, 1, 1],
+        };
+        return pattern.iter().map(|on| *on == 1).collect();
+    }
+
+    (1..=count).map(|index| index <= slot as usize).collect()
+}
+
+pub(crate) fn device_matches_address(device: &Path, addr: &str) -> bool {
+    if read_uniq(device).is_some_and(|uniq| uniq.eq_ignore_ascii_case(addr)) {
+        return true;
+    }
+
+    let Ok(inputs) = fs::read_dir(device.join("input")) else {
+        return false;
+    };
+
+    inputs
+        .flatten()
+        .any(|input| read_uniq(&input.path()).is_some_and(|uniq| uniq.eq_ignore_ascii_case(addr)))
+}
+
+fn read_uniq(device: &Path) -> Option<String> {
+    fs::read_to_string(device.join("uniq"))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
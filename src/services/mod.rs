@@ -1,7 +1,37 @@
+pub mod activity;
+pub mod agent;
+pub mod availability;
 pub mod bluetooth;
+pub mod bluez;
+pub mod compat;
+pub mod diagnostics;
+pub mod idle_inhibit;
+pub mod input;
+pub mod leds;
+pub mod notify;
+pub mod presence;
+pub mod sixaxis;
+pub mod sleep;
 
+pub use activity::ActivityMonitor;
+pub use availability::{bluez_available, receive_bluez_availability};
 pub use bluetooth::{
-    connect_device, disconnect_device, discover_device_by_address, list_connected_devices,
-    list_paired_devices, pair_device, read_ps_controller_battery, remove_device,
-    rename_paired_device, trust_device,
+    Discovery, DiscoveryFilter, ListFilter, NearbyDevice, connect_device, disconnect_device,
+    discover_device_by_address, get_device_property, is_game_controller, list_adapters,
+    list_connected_devices, list_nearby_gamepads, list_paired_devices, pair_device,
+    read_device_state, read_ps_controller_battery, remove_device, rename_paired_device,
+    set_device_property, set_discovering, start_discovery, trust_device,
 };
+pub use bluez::Bluez;
+pub use compat::{
+    ControllerModel, ControllerReport, Finding, apply_fix, check_compatibility, detect_model,
+    restart_bluetooth,
+};
+pub use diagnostics::collect_diagnostics;
+pub use idle_inhibit::{IdleInhibitor, inhibit_idle};
+pub use input::{InputNodeKind, list_input_nodes, set_input_inhibited};
+pub use leds::{PLAYER_SLOTS, set_player_leds};
+pub use notify::send_notification;
+pub use presence::{PresenceEvent, receive_presence_events};
+pub use sixaxis::{list_untrusted_sixaxis, receive_untrusted_sixaxis};
+pub use sleep::{SleepDelay, receive_prepare_for_sleep, take_sleep_delay};
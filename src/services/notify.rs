@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+use zbus::zvariant::Value;
+use zbus::{Connection, Proxy};
+
+const APP_NAME: &str = "Cosmic Bluetooth Gamepad";
+const APP_ICON: &str = "applications-games-symbolic";
+
+pub async fn send_notification(summary: &str, body: &str) -> zbus::Result<u32> {
+    let conn = Connection::session().await?;
+    let proxy = Proxy::new(
+        &conn,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+    )
+    .await?;
+
+    let actions: Vec<&str> = Vec::new();
+    let hints: HashMap<&str, Value<'_>> = HashMap::new();
+    proxy
+        .call(
+            "Notify",
+            &(
+                APP_NAME, 0u32, APP_ICON, summary, body, actions, hints, -1i32,
+            ),
+        )
+        .await
+}
@@ -0,0 +1,76 @@
+use futures_util::{Stream, StreamExt, future, stream};
+use std::collections::HashMap;
+use zbus::message::Type;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::{Connection, MatchRule, Message, MessageStream};
+
+#[derive(Debug, Clone)]
+pub enum PresenceEvent {
+    Advertising(String),
+    Connected(String, bool),
+}
+
+pub async fn receive_presence_events(
+    conn: &Connection,
+) -> zbus::Result<impl Stream<Item = PresenceEvent> + use<>> {
+    let changed = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender("org.bluez")?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path_namespace("/org/bluez")?
+        .build();
+    let added = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender("org.bluez")?
+        .interface("org.freedesktop.DBus.ObjectManager")?
+        .member("InterfacesAdded")?
+        .build();
+
+    let changed = MessageStream::for_match_rule(changed, conn, None).await?;
+    let added = MessageStream::for_match_rule(added, conn, None).await?;
+
+    Ok(stream::select(changed, added)
+        .filter_map(|msg| future::ready(msg.ok().and_then(|msg| parse_event(&msg)))))
+}
+
+fn parse_event(msg: &Message) -> Option<PresenceEvent> {
+    let header = msg.header();
+    match header.member()?.as_str() {
+        "PropertiesChanged" => {
+            let addr = address_from_path(header.path()?.as_str())?;
+            let (iface, changed, _invalidated): (String, HashMap<String, OwnedValue>, Vec<String>) =
+                msg.body().deserialize().ok()?;
+            if iface != "org.bluez.Device1" {
+                return None;
+            }
+
+            if let Some(connected) = changed.get("Connected") {
+                let connected = bool::try_from(connected).ok()?;
+                return Some(PresenceEvent::Connected(addr, connected));
+            }
+
+            changed
+                .contains_key("RSSI")
+                .then_some(PresenceEvent::Advertising(addr))
+        }
+        "InterfacesAdded" => {
+            let (path, ifaces): (
+                OwnedObjectPath,
+                HashMap<String, HashMap<String, OwnedValue>>,
+            ) = msg.body().deserialize().ok()?;
+            let dev = ifaces.get("org.bluez.Device1")?;
+
+            dev.contains_key("RSSI")
+                .then(|| address_from_path(path.as_str()))
+                .flatten()
+                .map(PresenceEvent::Advertising)
+        }
+        _ => None,
+    }
+}
+
+fn address_from_path(path: &str) -> Option<String> {
+    let (_, device) = path.rsplit_once("/dev_")?;
+    (!device.contains('/')).then(|| device.replace('_', ":"))
+}
@@ -0,0 +1,165 @@
+use super::Bluez;
+use super::bluez::Properties;
+use super::compat::{ControllerModel, detect_model};
+use futures_util::{Stream, StreamExt, future, stream};
+use std::collections::HashMap;
+use zbus::message::Type;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{MatchRule, Message, MessageStream};
+
+// Sixaxis controllers that BlueZ's sixaxis plugin has set up over USB but nobody has trusted yet,
+// which keeps their first Bluetooth connection from being accepted.
+pub fn list_untrusted_sixaxis(bluez: &Bluez) -> Vec<(String, String)> {
+    bluez
+        .objects_with("org.bluez.Device1")
+        .into_iter()
+        .filter_map(|(_path, dev)| untrusted_sixaxis(&dev))
+        .collect()
+}
+
+// Only a new device or a change to Trusted or Modalias can turn up another untrusted Sixaxis, so
+// RSSI and battery updates don't trigger a re-scan.
+pub async fn receive_untrusted_sixaxis(
+    bluez: &Bluez,
+) -> zbus::Result<impl Stream<Item = (String, String)> + use<>> {
+    let changed = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender("org.bluez")?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path_namespace("/org/bluez")?
+        .build();
+    let added = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender("org.bluez")?
+        .interface("org.freedesktop.DBus.ObjectManager")?
+        .member("InterfacesAdded")?
+        .build();
+
+    let conn = bluez.connection();
+    let changed = MessageStream::for_match_rule(changed, conn, None).await?;
+    let added = MessageStream::for_match_rule(added, conn, None).await?;
+
+    let bluez = bluez.clone();
+    Ok(stream::select(changed, added)
+        .filter_map(move |msg| future::ready(msg.ok().and_then(|msg| parse_event(&bluez, &msg)))))
+}
+
+fn parse_event(bluez: &Bluez, msg: &Message) -> Option<(String, String)> {
+    let header = msg.header();
+    let dev = match header.member()?.as_str() {
+        "PropertiesChanged" => {
+            let (iface, changed, _invalidated): (String, Properties, Vec<String>) =
+                msg.body().deserialize().ok()?;
+            if iface != "org.bluez.Device1"
+                || !(changed.contains_key("Trusted") || changed.contains_key("Modalias"))
+            {
+                return None;
+            }
+
+            // The cache may not have applied this signal yet, so the change goes on top of it.
+            let path = OwnedObjectPath::from(header.path()?.to_owned());
+            let mut dev = bluez
+                .objects_with("org.bluez.Device1")
+                .into_iter()
+                .find(|(known, _)| *known == path)
+                .map(|(_, dev)| dev)
+                .unwrap_or_default();
+            dev.extend(changed);
+            dev
+        }
+        "InterfacesAdded" => {
+            let (_path, mut ifaces): (OwnedObjectPath, HashMap<String, Properties>) =
+                msg.body().deserialize().ok()?;
+            ifaces.remove("org.bluez.Device1")?
+        }
+        _ => return None,
+    };
+
+    untrusted_sixaxis(&dev)
+}
+
+fn untrusted_sixaxis(dev: &Properties) -> Option<(String, String)> {
+    let name = get_string(dev, "Alias").or_else(|| get_string(dev, "Name"));
+    let model = detect_model(
+        get_string(dev, "Modalias").as_deref(),
+        name.as_deref().unwrap_or_default(),
+    );
+    let trusted = dev
+        .get("Trusted")
+        .and_then(|value| bool::try_from(value).ok())
+        .unwrap_or(false);
+    if model != ControllerModel::DualShock3 || trusted {
+        return None;
+    }
+
+    Some((
+        get_string(dev, "Address")?,
+        name.unwrap_or_else(|| "<unnamed>".into()),
+    ))
+}
+
+fn get_string(props: &Properties, key: &str) -> Option<String> {
+    props
+        .get(key)
+        .and_then(|value| String::try_from(value.clone()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::{OwnedValue, Value};
+
+    fn device(modalias: Option<&str>, name: &str, trusted: bool) -> Properties {
+        let mut dev = Properties::new();
+        let string = |value: &str| OwnedValue::try_from(Value::from(value)).unwrap();
+        dev.insert("Address".into(), string("00:1B:FB:00:00:01"));
+        dev.insert("Alias".into(), string(name));
+        dev.insert("Trusted".into(), OwnedValue::from(trusted));
+        if let Some(modalias) = modalias {
+            dev.insert("Modalias".into(), string(modalias));
+        }
+        dev
+    }
+
+    #[test]
+    fn finds_untrusted_dualshock3() {
+        let expected = Some((
+            "00:1B:FB:00:00:01".into(),
+            "PLAYSTATION(R)3 Controller".into(),
+        ));
+        assert_eq!(
+            untrusted_sixaxis(&device(
+                Some("usb:v054Cp0268d0100"),
+                "PLAYSTATION(R)3 Controller",
+                false
+            )),
+            expected
+        );
+        // Set up by the sixaxis plugin before the modalias is known.
+        assert_eq!(
+            untrusted_sixaxis(&device(None, "PLAYSTATION(R)3 Controller", false)),
+            expected
+        );
+    }
+
+    #[test]
+    fn skips_trusted_and_other_controllers() {
+        assert_eq!(
+            untrusted_sixaxis(&device(
+                Some("usb:v054Cp0268d0100"),
+                "PLAYSTATION(R)3 Controller",
+                true
+            )),
+            None
+        );
+        assert_eq!(
+            untrusted_sixaxis(&device(
+                Some("usb:v054Cp0CE6d0100"),
+                "DualSense Wireless Controller",
+                false
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use futures_util::{Stream, StreamExt, future};
+use zbus::zvariant::OwnedFd;
+use zbus::{Connection, Proxy};
+
+#[derive(Debug)]
+pub struct SleepDelay {
+    _fd: OwnedFd,
+}
+
+pub async fn take_sleep_delay(conn: &Connection, reason: &str) -> zbus::Result<SleepDelay> {
+    let proxy = login_manager_proxy(conn).await?;
+    let fd: OwnedFd = proxy
+        .call(
+            "Inhibit",
+            &("sleep", "Cosmic Bluetooth Gamepad", reason, "delay"),
+        )
+        .await?;
+    Ok(SleepDelay { _fd: fd })
+}
+
+pub async fn receive_prepare_for_sleep(
+    conn: &Connection,
+) -> zbus::Result<impl Stream<Item = bool> + use<>> {
+    let proxy = login_manager_proxy(conn).await?;
+    let signals = proxy.receive_signal("PrepareForSleep").await?;
+
+    Ok(signals.filter_map(|message| future::ready(message.body().deserialize::<bool>().ok())))
+}
+
+async fn login_manager_proxy(conn: &Connection) -> zbus::Result<Proxy<'static>> {
+    Proxy::new(
+        conn,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    )
+    .await
+}